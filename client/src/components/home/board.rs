@@ -1,9 +1,9 @@
 use crate::components::home::card::Card;
-use model::book::{Book, Subject};
+use model::book::Book;
 
 use gloo::console::log;
 use wasm_bindgen_futures::spawn_local;
-use yew::{function_component, html, use_effect_with_deps, use_state, Callback, Html};
+use yew::{function_component, html, use_effect_with_deps, use_state, Html};
 
 #[function_component(Board)]
 pub fn board() -> Html {
@@ -1,4 +1,4 @@
-use yew::{function_component, html, use_state, Callback, Html, MouseEvent, Properties};
+use yew::{function_component, html, use_state, Callback, Html, Properties};
 
 #[derive(Properties, PartialEq)]
 pub struct Props {
@@ -2,7 +2,7 @@ use model::book::{Bookshelf, Subject};
 
 use gloo::console::log;
 use wasm_bindgen_futures::spawn_local;
-use yew::{function_component, html, use_effect_with_deps, use_state, Callback, Html};
+use yew::{function_component, html, use_effect_with_deps, use_state, Html};
 
 use crate::components::home::side_card::SideCard;
 
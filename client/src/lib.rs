@@ -5,7 +5,6 @@ use yew::{function_component, html, use_state, Callback, Html};
 use crate::views::home::Home;
 
 mod components;
-mod utils;
 mod views;
 
 #[derive(PartialEq, Clone)]
@@ -1 +0,0 @@
-pub const COLORS: &str = "text-primary dark:text-secondary bg-secondary dark:text-primary";
@@ -1,10 +1,19 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::ops::AddAssign;
+use std::sync::OnceLock;
 
 use serde::{Deserialize, Serialize};
 //use sqlx::FromRow;
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::frequency_laws::{HeapsFit, ZipfFit};
+use crate::ngrams::Ngrams;
+use crate::readability::Readability;
+use crate::richness::{interned_moving_average_ttr, LexicalRichness, MATTR_WINDOW};
+use crate::segmentation::TextStructure;
 use crate::stop_words::is_stopword;
+use crate::summary::Summary;
 
 // pub struct Record {
 //     pub title: Option<String>,
@@ -42,10 +51,35 @@ pub struct Bookshelf {
     pub shelf_id: i32,
 }
 
+/// Serialized as the word counts and the measures derived from them. The
+/// JSON no longer carries a `sorted_words` list next to `word_map`, which
+/// doubled its size; rank `word_map` instead.
 #[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
 pub struct Analytics {
+    /// Occurrences of every word, stopwords included. Use
+    /// [`Analytics::sorted_words`] for a ranking.
     pub word_map: HashMap<String, u32>,
-    pub sorted_words: Vec<(String, u32)>,
+    pub structure: TextStructure,
+    pub readability: Readability,
+    pub richness: LexicalRichness,
+    pub zipf: ZipfFit,
+    pub heaps: HeapsFit,
+    pub ngrams: Option<Ngrams>,
+    /// Extractive summary, filled in when first requested.
+    pub summary: Option<Summary>,
+    #[serde(skip)]
+    ranking: Ranking,
+}
+
+/// The non-stopwords of `word_map` sorted by [`Analytics::sorted_words`] on
+/// first use. Not part of the value: it is skipped when comparing.
+#[derive(Clone, Default, Debug)]
+struct Ranking(OnceLock<Vec<(String, u32)>>);
+
+impl PartialEq for Ranking {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
@@ -64,60 +98,279 @@ pub struct Book {
     pub analytics: Option<Analytics>,
 }
 
-impl Analytics {
-    pub fn new(content: &String) -> Self {
-        let mut word_map = HashMap::new();
-        let word_vec: Vec<String> = content
-            .split_whitespace()
-            .map(|word| {
-                word.chars()
-                    .filter(|ch| ch.is_alphabetic())
-                    .collect::<String>()
-                    .to_lowercase()
-            })
-            .collect();
-
-        for word in word_vec {
-            *word_map.entry(word).or_insert(0) += 1;
+/// A token together with the byte range of the whitespace-delimited word
+/// it was taken from.
+#[derive(PartialEq, Clone, Debug)]
+pub struct TokenSpan {
+    pub start: usize,
+    pub end: usize,
+    pub token: String,
+}
+
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|ch| ch.is_alphabetic())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Splits text into the lowercase, purely alphabetic tokens that every
+/// analysis in this crate counts. Tokens without any letters are dropped.
+pub fn tokenize(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .split_whitespace()
+        .map(normalize)
+        .filter(|word| !word.is_empty())
+}
+
+/// Same tokens as [`tokenize`], but keeping where each one sits in `content`.
+pub fn token_spans(content: &str) -> Vec<TokenSpan> {
+    let mut spans = Vec::new();
+    let mut start = None;
+
+    for (index, ch) in content.char_indices().chain([(content.len(), ' ')]) {
+        match (ch.is_whitespace(), start) {
+            (false, None) => start = Some(index),
+            (true, Some(begin)) => {
+                let token = normalize(&content[begin..index]);
+                if !token.is_empty() {
+                    spans.push(TokenSpan {
+                        start: begin,
+                        end: index,
+                        token,
+                    });
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    spans
+}
+
+/// The token of a single whitespace-delimited word, borrowed from the text
+/// when it is already normalized, which is the common case. Same result as
+/// `normalize`.
+fn normalize_borrowed(word: &str) -> Cow<'_, str> {
+    let core = word.trim_matches(|ch: char| !ch.is_alphabetic());
+    if core.bytes().all(|byte| byte.is_ascii_lowercase()) {
+        Cow::Borrowed(core)
+    } else {
+        Cow::Owned(normalize(word))
+    }
+}
+
+/// Number of tokens [`tokenize`] would produce, without allocating them.
+pub fn count_tokens(content: &str) -> usize {
+    content
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphabetic))
+        .count()
+}
+
+/// A text's tokens as indices into its vocabulary, so that every distinct
+/// word is stored only once and mostly borrowed from the text itself.
+pub struct InternedTokens<'a> {
+    /// Maps every distinct token to its id.
+    pub vocabulary: HashMap<Cow<'a, str>, u32>,
+    /// Occurrences of each token, indexed by id.
+    pub counts: Vec<u32>,
+    /// The token ids in reading order.
+    pub ids: Vec<u32>,
+}
+
+impl<'a> InternedTokens<'a> {
+    pub fn new(content: &'a str) -> Self {
+        let mut vocabulary: HashMap<Cow<'a, str>, u32> = HashMap::new();
+        let mut counts: Vec<u32> = Vec::new();
+        let mut ids = Vec::new();
+
+        for word in content.split_whitespace() {
+            let token = normalize_borrowed(word);
+            if token.is_empty() {
+                continue;
+            }
+            let id = match vocabulary.get(token.as_ref()) {
+                Some(&id) => id,
+                None => {
+                    let id = counts.len() as u32;
+                    vocabulary.insert(token, id);
+                    counts.push(0);
+                    id
+                }
+            };
+            counts[id as usize] += 1;
+            ids.push(id);
         }
 
-        let mut sorted_words: Vec<(String, u32)> = word_map
+        Self {
+            vocabulary,
+            counts,
+            ids,
+        }
+    }
+
+    pub fn word_map(&self) -> HashMap<String, u32> {
+        self.vocabulary
             .iter()
-            .filter(|&(word, _)| !is_stopword(word))
-            .map(|(word, &count)| (word.clone(), count))
-            .collect();
+            .map(|(word, &id)| (word.to_string(), self.counts[id as usize]))
+            .collect()
+    }
+}
 
-        sorted_words.sort_by(|a, b| b.1.cmp(&a.1));
+/// Occurrences of every token in `content`.
+pub fn count_words(content: &str) -> HashMap<String, u32> {
+    let mut word_map: HashMap<String, u32> = HashMap::new();
+    for word in content.split_whitespace() {
+        let token = normalize_borrowed(word);
+        if token.is_empty() {
+            continue;
+        }
+        match word_map.get_mut(token.as_ref()) {
+            Some(count) => *count += 1,
+            None => {
+                word_map.insert(token.into_owned(), 1);
+            }
+        }
+    }
+    word_map
+}
+
+/// The non-stopwords of a frequency table, unsorted.
+fn ranked_words(word_map: &HashMap<String, u32>) -> Vec<(&str, u32)> {
+    word_map
+        .iter()
+        .filter(|&(word, _)| !is_stopword(word))
+        .map(|(word, &count)| (word.as_str(), count))
+        .collect()
+}
+
+/// The `amount` most frequent words of `word_map` that are not stopwords,
+/// most frequent first.
+pub(crate) fn top_words(word_map: &HashMap<String, u32>, amount: u32) -> Vec<(&str, u32)> {
+    let mut words = ranked_words(word_map);
+    let amount = (amount as usize).min(words.len());
+    if amount > 0 && amount < words.len() {
+        words.select_nth_unstable_by(amount - 1, by_rank);
+    }
+    words.truncate(amount);
+    words.sort_unstable_by(by_rank);
+    words
+}
+
+/// Most frequent first, ties in alphabetical order.
+fn by_rank(a: &(&str, u32), b: &(&str, u32)) -> std::cmp::Ordering {
+    b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0))
+}
+
+impl Analytics {
+    pub fn new(content: &str) -> Self {
+        let tokens = InternedTokens::new(content);
+        let word_map = tokens.word_map();
+
+        let structure = TextStructure::new(content);
+        let readability = Readability::new(&word_map, structure.sentence_count);
+        let richness = LexicalRichness::from_word_map(
+            &word_map,
+            interned_moving_average_ttr(&tokens.ids, tokens.counts.len(), MATTR_WINDOW),
+        );
+        let zipf = ZipfFit::new(&word_map);
+        let heaps = HeapsFit::from_ids(&tokens.ids, tokens.counts.len());
 
         Self {
             word_map,
-            sorted_words,
+            structure,
+            readability,
+            richness,
+            zipf,
+            heaps,
+            ngrams: None,
+            summary: None,
+            ranking: Ranking::default(),
         }
     }
 
-    pub fn get_count(&self, word: &String) -> Option<&u32> {
+    /// Adds the counts of another text, as if it had been appended to this
+    /// one. Everything derived from word frequencies is recomputed exactly;
+    /// the moving-average TTR is averaged weighted by length and the Heaps
+    /// curve is extended approximately. N-grams cannot be combined from
+    /// their truncated lists and are dropped.
+    pub fn merge(&mut self, other: &Analytics) {
+        let own_tokens = self.get_total_word_count() as f64;
+        let other_tokens = other.get_total_word_count() as f64;
+
+        for (word, &count) in &other.word_map {
+            match self.word_map.get_mut(word) {
+                Some(own) => *own += count,
+                None => {
+                    self.word_map.insert(word.clone(), count);
+                }
+            }
+        }
+
+        self.structure.merge(&other.structure);
+        self.readability = Readability::new(&self.word_map, self.structure.sentence_count);
+
+        let moving_average_ttr = if own_tokens + other_tokens > 0.0 {
+            (self.richness.moving_average_ttr * own_tokens
+                + other.richness.moving_average_ttr * other_tokens)
+                / (own_tokens + other_tokens)
+        } else {
+            0.0
+        };
+        self.richness = LexicalRichness::from_word_map(&self.word_map, moving_average_ttr);
+        self.zipf = ZipfFit::new(&self.word_map);
+        self.heaps.merge(&other.heaps, self.word_map.len());
+        self.ngrams = None;
+        self.summary = None;
+        self.ranking = Ranking::default();
+    }
+
+    pub fn get_count(&self, word: &str) -> Option<&u32> {
         let lower_word = word.to_lowercase();
         self.word_map.get(&lower_word)
     }
 
-    pub fn get_top_words(&self, amount: u32) -> Vec<(usize, &(String, u32))> {
-        let len = self.sorted_words.len();
-        let amount = amount as usize;
+    /// All non-stopwords, most frequent first. They are sorted on the first
+    /// call and kept until [`Analytics::merge`] changes the counts, so
+    /// `word_map` must not be changed directly after this is called.
+    pub fn sorted_words(&self) -> Vec<(&str, u32)> {
+        self.ranking()
+            .iter()
+            .map(|(word, count)| (word.as_str(), *count))
+            .collect()
+    }
 
-        if amount > len {
-            self.sorted_words
-                .iter()
-                .enumerate()
-                .map(|(index, word)| (index + 1, word))
+    fn ranking(&self) -> &[(String, u32)] {
+        self.ranking.0.get_or_init(|| {
+            let mut words = ranked_words(&self.word_map);
+            words.sort_unstable_by(by_rank);
+            words
+                .into_iter()
+                .map(|(word, count)| (word.to_string(), count))
                 .collect()
-        } else {
-            self.sorted_words
+        })
+    }
+
+    /// The `amount` most frequent non-stopwords with their one-based rank.
+    /// Reads the ranking cached by [`Analytics::sorted_words`] if there is
+    /// one; otherwise only the head is sorted, which is cheap even for a
+    /// large vocabulary.
+    pub fn get_top_words(&self, amount: u32) -> Vec<(usize, (&str, u32))> {
+        let words = match self.ranking.0.get() {
+            Some(ranking) => ranking
                 .iter()
-                .enumerate()
-                .take(amount)
-                .map(|(index, word)| (index + 1, word))
-                .collect()
-        }
+                .take(amount as usize)
+                .map(|(word, count)| (word.as_str(), *count))
+                .collect(),
+            None => top_words(&self.word_map, amount),
+        };
+        words
+            .into_iter()
+            .enumerate()
+            .map(|(index, word)| (index + 1, word))
+            .collect()
     }
 
     pub fn get_total_word_count(&self) -> u32 {
@@ -132,26 +385,117 @@ impl Analytics {
     }
 }
 
+impl AddAssign<&Analytics> for Analytics {
+    fn add_assign(&mut self, other: &Analytics) {
+        self.merge(other);
+    }
+}
+
+impl AddAssign for Analytics {
+    fn add_assign(&mut self, other: Analytics) {
+        self.merge(&other);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_normal() {
-        let analytics = Analytics::new(&"Hello, world! Hello, everyone!".to_string());
+        let analytics = Analytics::new("Hello, world! Hello, everyone!");
 
         assert_eq!(analytics.word_map.get("hello"), Some(&2));
         assert_eq!(analytics.word_map.get("world"), Some(&1));
         assert_eq!(analytics.word_map.get("everyone"), Some(&1));
-        assert_eq!(analytics.sorted_words[0], ("hello".to_string(), 2));
+        assert_eq!(analytics.sorted_words()[0], ("hello", 2));
+    }
+
+    #[test]
+    fn test_count_words_matches_word_map() {
+        let content = "The cat saw the other cat. THE END";
+
+        assert_eq!(count_words(content), Analytics::new(content).word_map);
+        assert_eq!(count_words(content).get("the"), Some(&3));
+    }
+
+    #[test]
+    fn test_merge_matches_concatenation() {
+        let first = "Call me Ishmael. Some years ago I went to sea.\n\nThe whale swam.";
+        let second = "The sea was calm. Ishmael slept.";
+
+        let mut merged = Analytics::new(first);
+        merged.merge(&Analytics::new(second));
+        let whole = Analytics::new(&format!("{first}\n\n{second}"));
+
+        assert_eq!(merged.word_map, whole.word_map);
+        assert_eq!(merged.sorted_words(), whole.sorted_words());
+        assert_eq!(merged.structure, whole.structure);
+        assert_eq!(merged.readability, whole.readability);
+        assert_eq!(merged.zipf, whole.zipf);
+        assert_eq!(merged.richness.yules_k, whole.richness.yules_k);
+        assert_eq!(
+            merged.heaps.vocabulary_growth.last(),
+            whole.heaps.vocabulary_growth.last()
+        );
+    }
+
+    #[test]
+    fn test_merge_resets_ranking() {
+        let mut merged = Analytics::new("whale whale sea");
+        assert_eq!(merged.sorted_words()[0], ("whale", 2));
+        merged.merge(&Analytics::new("sea sea"));
+
+        assert_eq!(merged.sorted_words()[0], ("sea", 3));
+        assert_eq!(merged.get_top_words(1), vec![(1, ("sea", 3))]);
+    }
+
+    #[test]
+    fn test_merge_into_empty() {
+        let analytics = Analytics::new("The cat sat on the mat.");
+        let mut merged = Analytics::new("");
+        merged.merge(&analytics);
+
+        assert_eq!(merged.word_map, analytics.word_map);
+        assert_eq!(merged.richness, analytics.richness);
+        assert_eq!(merged.heaps, analytics.heaps);
+    }
+
+    #[test]
+    fn test_borrowed_tokens_match_normalize() {
+        for word in [
+            "whale",
+            "whale,",
+            "\"Whale!\"",
+            "don't",
+            "--",
+            "Señor",
+            "ΣΟΦΟΣ",
+            "x1y",
+        ] {
+            assert_eq!(normalize_borrowed(word), normalize(word), "{word}");
+        }
+        assert!(matches!(
+            normalize_borrowed("(whale)"),
+            Cow::Borrowed("whale")
+        ));
+    }
+
+    #[test]
+    fn test_interned_tokens() {
+        let tokens = InternedTokens::new("The whale, the sea; THE whale.");
+
+        assert_eq!(tokens.ids, vec![0, 1, 0, 2, 0, 1]);
+        assert_eq!(tokens.counts, vec![3, 2, 1]);
+        assert_eq!(count_tokens("The whale, -- 42 sea"), 3);
     }
 
     #[test]
     fn test_empty() {
-        let analytics = Analytics::new(&"".to_string());
+        let analytics = Analytics::new("");
 
         assert_eq!(analytics.word_map.len(), 0);
-        assert_eq!(analytics.sorted_words.len(), 0);
+        assert_eq!(analytics.sorted_words().len(), 0);
     }
 
     #[test]
@@ -163,13 +507,13 @@ mod tests {
         assert_eq!(analytics.word_map.get("world"), Some(&3));
 
         assert!(analytics
-            .sorted_words
+            .sorted_words()
             .iter()
-            .any(|&(ref s, count)| s == "hello" && count == 3));
+            .any(|&(s, count)| s == "hello" && count == 3));
         assert!(analytics
-            .sorted_words
+            .sorted_words()
             .iter()
-            .any(|&(ref s, count)| s == "world" && count == 3));
+            .any(|&(s, count)| s == "world" && count == 3));
     }
 
     #[test]
@@ -177,8 +521,67 @@ mod tests {
         let content = "the quick brown fox jumps over the lazy dog".to_string();
         let analytics = Analytics::new(&content);
         assert_eq!(analytics.word_map.get("the"), Some(&2));
-        let in_sorted = analytics.sorted_words.iter().any(|(word, _)| word == "the");
-        assert_eq!(in_sorted, false);
+        let in_sorted = analytics
+            .sorted_words()
+            .iter()
+            .any(|&(word, _)| word == "the");
+        assert!(!in_sorted);
+    }
+
+    #[test]
+    fn test_non_alphabetic_tokens_are_dropped() {
+        let analytics = Analytics::new("Chapter 12 -- hello *** world");
+
+        assert_eq!(analytics.word_map.get(""), None);
+        assert_eq!(analytics.get_total_word_count(), 3);
+    }
+
+    #[test]
+    fn test_structure() {
+        let content = "Mr. Bennet laughed.\nHe was a\nwise man.\n\nShe was not.";
+        let analytics = Analytics::new(content);
+
+        assert_eq!(analytics.structure.paragraph_count, 2);
+        assert_eq!(analytics.structure.sentence_count, 3);
+    }
+
+    #[test]
+    fn test_readability() {
+        let analytics = Analytics::new("The cat sat on the mat. The dog sat on the rug.");
+
+        assert!((analytics.readability.flesch_reading_ease - 116.145).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_richness() {
+        let analytics = Analytics::new("The cat saw the dog, and the dog saw the cat run.");
+
+        assert_eq!(analytics.richness.type_token_ratio, 0.5);
+        assert_eq!(analytics.richness.hapax_legomena, 2);
+    }
+
+    #[test]
+    fn test_frequency_laws() {
+        let analytics = Analytics::new("a rose is a rose is a rose");
+
+        assert_eq!(analytics.zipf.rank_frequency[0].y, 3.0);
+        assert_eq!(analytics.heaps.vocabulary_growth.len(), 8);
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let content = "  Hello, world!\n -- Hello";
+        let spans = token_spans(content);
+
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| span.token.as_str())
+                .collect::<Vec<_>>(),
+            tokenize(content).collect::<Vec<_>>()
+        );
+        assert_eq!(&content[spans[1].start..spans[1].end], "world!");
+        assert_eq!(spans[2].start, 20);
     }
 
     #[test]
@@ -186,13 +589,13 @@ mod tests {
         let content = "Hello hello world world world".to_string();
         let analytics = Analytics::new(&content);
 
-        let count = analytics.get_count(&"hello".to_string());
+        let count = analytics.get_count("hello");
         assert_eq!(count, Some(&2));
 
-        let count = analytics.get_count(&"WORLD".to_string());
+        let count = analytics.get_count("WORLD");
         assert_eq!(count, Some(&3));
 
-        let count = analytics.get_count(&"goodbye".to_string());
+        let count = analytics.get_count("goodbye");
         assert_eq!(count, None);
     }
 
@@ -203,13 +606,7 @@ mod tests {
 
         let top_2_words = analytics.get_top_words(2);
         assert_eq!(top_2_words.len(), 2);
-        assert_eq!(
-            top_2_words,
-            vec![
-                (1, &("hello".to_string(), 3)),
-                (2, &("world".to_string(), 2))
-            ]
-        );
+        assert_eq!(top_2_words, vec![(1, ("hello", 3)), (2, ("world", 2))]);
     }
 
     #[test]
@@ -221,11 +618,7 @@ mod tests {
         assert_eq!(top_5_words.len(), 3); // only 3 unique words in content
         assert_eq!(
             top_5_words,
-            vec![
-                (1, &("hello".to_string(), 3)),
-                (2, &("world".to_string(), 2)),
-                (3, &("test".to_string(), 1)),
-            ]
+            vec![(1, ("hello", 3)), (2, ("world", 2)), (3, ("test", 1)),]
         );
     }
 
@@ -0,0 +1,289 @@
+use crate::book::Analytics;
+
+/// Text is analyzed in batches of at least this many bytes. Smaller batches
+/// would make the moving-average TTR and the Heaps curve, which are only
+/// approximately mergeable, less accurate.
+pub(crate) const BATCH_SIZE: usize = 1 << 16;
+
+/// Analyzes `content` in the batches [`AnalyticsBuilder`] would cut it into
+/// and merges them in order, so the result is the same as streaming the
+/// text, however the stream is chunked. Analytics that are stored should
+/// come from here, the builder or `parallel::analyze`, which all agree;
+/// [`Analytics::new`] approximates the moving-average TTR and the Heaps
+/// curve differently on long texts.
+pub fn analyze(content: &str) -> Analytics {
+    merge(batches(content, BATCH_SIZE).into_iter().map(Analytics::new))
+}
+
+/// Merges analytics of consecutive batches, first to last.
+pub(crate) fn merge(batches: impl Iterator<Item = Analytics>) -> Analytics {
+    batches
+        .reduce(|mut total, batch| {
+            total += batch;
+            total
+        })
+        .unwrap_or_else(|| Analytics::new(""))
+}
+
+/// Builds [`Analytics`] from text that arrives in chunks, e.g. from a
+/// streamed download, without holding the whole text in memory. Text is
+/// cut into batches at paragraph breaks, so words and sentences split
+/// across chunks are counted once.
+pub struct AnalyticsBuilder {
+    analytics: Option<Analytics>,
+    pending: String,
+    /// Length of the complete lines of `pending` already looked at.
+    scanned: usize,
+    undecoded: Vec<u8>,
+    batch_size: usize,
+}
+
+impl Default for AnalyticsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnalyticsBuilder {
+    pub fn new() -> Self {
+        Self::with_batch_size(BATCH_SIZE)
+    }
+
+    fn with_batch_size(batch_size: usize) -> Self {
+        Self {
+            analytics: None,
+            pending: String::new(),
+            scanned: 0,
+            undecoded: Vec::new(),
+            batch_size,
+        }
+    }
+
+    pub fn push(&mut self, chunk: &str) {
+        self.pending.push_str(chunk);
+        while let Some(newline) = self.pending[self.scanned..].find('\n') {
+            let line_end = self.scanned + newline + 1;
+            let last_line = &self.pending[self.scanned..line_end];
+            match batch_end(&self.pending[..line_end], last_line, self.batch_size) {
+                Some(end) => {
+                    let rest = self.pending.split_off(end);
+                    let batch = std::mem::replace(&mut self.pending, rest);
+                    self.add_batch(&batch);
+                    self.scanned = 0;
+                }
+                None => self.scanned = line_end,
+            }
+        }
+    }
+
+    /// Like [`AnalyticsBuilder::push`] for raw UTF-8, which may split a
+    /// character across chunks. Invalid sequences become U+FFFD.
+    pub fn push_bytes(&mut self, chunk: &[u8]) {
+        self.undecoded.extend_from_slice(chunk);
+
+        let mut decoded = String::new();
+        let mut rest: &[u8] = &self.undecoded;
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    decoded.push_str(valid);
+                    rest = &[];
+                    break;
+                }
+                Err(error) => {
+                    let (valid, after) = rest.split_at(error.valid_up_to());
+                    decoded.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match error.error_len() {
+                        Some(invalid) => {
+                            decoded.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[invalid..];
+                        }
+                        // An incomplete character at the end: wait for more.
+                        None => {
+                            rest = after;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.undecoded = rest.to_vec();
+        self.push(&decoded);
+    }
+
+    pub fn build(mut self) -> Analytics {
+        if !self.undecoded.is_empty() {
+            let rest = String::from_utf8_lossy(&self.undecoded).into_owned();
+            self.pending.push_str(&rest);
+        }
+        let pending = std::mem::take(&mut self.pending);
+        self.add_batch(&pending);
+        self.analytics.unwrap_or_else(|| Analytics::new(""))
+    }
+
+    fn add_batch(&mut self, batch: &str) {
+        if batch.trim().is_empty() {
+            return;
+        }
+        let batch = Analytics::new(batch);
+        match &mut self.analytics {
+            Some(total) => *total += batch,
+            None => self.analytics = Some(batch),
+        }
+    }
+}
+
+/// Cuts `content` into the batches of at least `size` bytes that
+/// [`AnalyticsBuilder`] analyzes, leaving out those with only whitespace.
+pub(crate) fn batches(content: &str, size: usize) -> Vec<&str> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        offset += line.len();
+        if !line.ends_with('\n') {
+            break;
+        }
+        if let Some(end) = batch_end(&content[start..offset], line, size) {
+            batches.push(&content[start..start + end]);
+            start += end;
+        }
+    }
+    batches.push(&content[start..]);
+
+    batches.retain(|batch| !batch.trim().is_empty());
+    batches
+}
+
+/// Where to end a batch whose text so far is `text`, `last_line` being its
+/// newly completed last line: at a paragraph break once the batch is
+/// `size` bytes long, or at a line break for text that runs on without
+/// blank lines.
+fn batch_end(text: &str, last_line: &str, size: usize) -> Option<usize> {
+    if text.len() < size {
+        return None;
+    }
+    if last_line.trim().is_empty() {
+        if let Some(end) = paragraph_break(text) {
+            return Some(end);
+        }
+    }
+    (text.len() >= 4 * size).then_some(text.len())
+}
+
+/// Byte offset just after the last blank line in `text`.
+pub(crate) fn paragraph_break(text: &str) -> Option<usize> {
+    let mut offset = text.len();
+    let mut after_blank = None;
+
+    for line in text.split_inclusive('\n').rev() {
+        if line.ends_with('\n') && line.trim().is_empty() && after_blank.is_none() {
+            after_blank = Some(offset);
+        }
+        offset -= line.len();
+        // Only a blank line preceded by text ends a paragraph.
+        if let Some(end) = after_blank {
+            if !line.trim().is_empty() {
+                return Some(end);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn novel() -> String {
+        (0..4000)
+            .map(|index| {
+                format!("Paragraph {index} tells of the whale\nand the sea. It ends here.\n\n")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunks_split_words_and_characters() {
+        let text = format!("Señor Quixote rode out.\n\n{}", novel());
+        let whole = Analytics::new(&text);
+
+        let mut builder = AnalyticsBuilder::new();
+        for chunk in text.as_bytes().chunks(997) {
+            builder.push_bytes(chunk);
+        }
+        let streamed = builder.build();
+
+        assert_eq!(streamed.word_map, whole.word_map);
+        assert_eq!(streamed.sorted_words(), whole.sorted_words());
+        assert_eq!(streamed.structure, whole.structure);
+        assert_eq!(streamed.readability, whole.readability);
+        assert!(
+            (streamed.richness.moving_average_ttr - whole.richness.moving_average_ttr).abs() < 0.01
+        );
+    }
+
+    #[test]
+    fn test_streaming_matches_analyze() {
+        let text = format!("Señor Quixote rode out.\n\n{}", novel());
+        let size = 3000;
+        let whole = merge(batches(&text, size).into_iter().map(Analytics::new));
+
+        for chunk_size in [1, 997, 4096, text.len()] {
+            let mut builder = AnalyticsBuilder::with_batch_size(size);
+            for chunk in text.as_bytes().chunks(chunk_size) {
+                builder.push_bytes(chunk);
+            }
+            assert_eq!(builder.build(), whole, "chunks of {chunk_size}");
+        }
+
+        let mut builder = AnalyticsBuilder::new();
+        builder.push(&text);
+        assert_eq!(builder.build(), analyze(&text));
+    }
+
+    #[test]
+    fn test_batches() {
+        let content = "One two.\n\nThree\nfour.\n\n\nFive.";
+
+        assert_eq!(
+            batches(content, 5),
+            vec!["One two.\n\n", "Three\nfour.\n\n", "\nFive."]
+        );
+        assert_eq!(batches(content, 1000), vec![content]);
+        // Without blank lines, at a line break once four times too long.
+        assert_eq!(batches("a b\nc d\ne", 2), vec!["a b\nc d\n", "e"]);
+        assert!(batches("", 5).is_empty());
+        assert!(batches(" \n\n", 1).is_empty());
+    }
+
+    #[test]
+    fn test_small_text() {
+        let mut builder = AnalyticsBuilder::new();
+        builder.push("The cat sa");
+        builder.push("t on the mat.");
+
+        assert_eq!(builder.build(), Analytics::new("The cat sat on the mat."));
+    }
+
+    #[test]
+    fn test_paragraph_break() {
+        assert_eq!(paragraph_break("One.\n\nTwo\n"), Some(6));
+        assert_eq!(paragraph_break("One.\r\n\r\nTwo"), Some(8));
+        assert_eq!(paragraph_break("\n\nOne line"), None);
+        assert_eq!(paragraph_break("No break\n"), None);
+    }
+
+    #[test]
+    fn test_add_assign() {
+        let mut total = Analytics::new("The whale.");
+        total += Analytics::new("The sea.");
+        total += &Analytics::new("The whale again.");
+
+        assert_eq!(total.get_count("whale"), Some(&2));
+        assert_eq!(total.get_count("the"), Some(&3));
+    }
+}
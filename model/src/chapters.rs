@@ -0,0 +1,363 @@
+use serde::{Deserialize, Serialize};
+
+use crate::book::{tokenize, Analytics};
+
+/// Words that introduce a numbered heading, e.g. "CHAPTER IV" or "Letter 3".
+const HEADING_KEYWORDS: [&str; 9] = [
+    "chapter", "book", "part", "letter", "volume", "stave", "canto", "section", "act",
+];
+/// Headings that stand on their own without a number.
+const UNNUMBERED_HEADINGS: [&str; 5] = [
+    "prologue",
+    "epilogue",
+    "preface",
+    "introduction",
+    "conclusion",
+];
+const NUMBER_WORDS: [&str; 30] = [
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+    "twenty",
+    "first",
+    "second",
+    "third",
+    "fourth",
+    "fifth",
+    "sixth",
+    "seventh",
+    "eighth",
+    "ninth",
+    "tenth",
+];
+const MAX_HEADING_LENGTH: usize = 80;
+/// Sections shorter than this are merged into the next chapter, so that a
+/// table of contents with a line under each entry does not make chapters.
+const MIN_CHAPTER_WORDS: usize = 20;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Chapter {
+    pub index: usize,
+    pub title: String,
+    /// Character offsets of the chapter, heading included, in the text.
+    pub start: usize,
+    pub end: usize,
+    pub word_count: u32,
+    pub character_count: usize,
+    pub top_words: Vec<(String, u32)>,
+}
+
+/// Splits a book body into `(title, text)` sections at chapter headings.
+/// Text before the first heading (title page, contents) is left out, as
+/// are sections made only of headings; a text without any headings comes
+/// back as a single untitled section.
+pub fn split_chapters(content: &str) -> Vec<(String, &str)> {
+    let mut headings: Vec<(usize, &str)> = Vec::new();
+    let mut offset = 0;
+    let mut after_blank = true;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if after_blank && is_heading(trimmed) {
+            headings.push((offset, trimmed));
+        }
+        after_blank = trimmed.is_empty();
+        offset += line.len();
+    }
+
+    let mut sections: Vec<(String, &str)> = Vec::new();
+    // Start of the short sections waiting to be merged into the next chapter.
+    let mut pending: Option<usize> = None;
+    for (index, &(start, title)) in headings.iter().enumerate() {
+        let end = headings
+            .get(index + 1)
+            .map_or(content.len(), |&(next, _)| next);
+        let words = body_words(&content[start..end]);
+
+        if words >= MIN_CHAPTER_WORDS {
+            let start = pending.take().unwrap_or(start);
+            sections.push((title.to_string(), &content[start..end]));
+        } else if words > 0 {
+            pending.get_or_insert(start);
+        }
+    }
+    // Short sections after the last chapter belong to it.
+    if let (Some(_), Some(last)) = (pending, sections.last_mut()) {
+        let last_start = last.1.as_ptr() as usize - content.as_ptr() as usize;
+        last.1 = &content[last_start..];
+    }
+
+    if sections.is_empty() && !content.trim().is_empty() {
+        sections.push((String::new(), content));
+    }
+
+    sections
+}
+
+impl Chapter {
+    /// Builds the table of contents of a book body, with each chapter's
+    /// length and its `top_words` most frequent words.
+    pub fn table_of_contents(content: &str, top_words: u32) -> Vec<Self> {
+        let mut chapters = Vec::new();
+        let mut chars_before = 0;
+        let mut counted_until = 0;
+
+        for (index, (title, text)) in split_chapters(content).into_iter().enumerate() {
+            let byte_start = text.as_ptr() as usize - content.as_ptr() as usize;
+            chars_before += content[counted_until..byte_start].chars().count();
+            counted_until = byte_start;
+
+            let character_count = text.chars().count();
+            let analytics = Analytics::new(text);
+
+            chapters.push(Self {
+                index,
+                title,
+                start: chars_before,
+                end: chars_before + character_count,
+                word_count: analytics.get_total_word_count(),
+                character_count,
+                top_words: analytics
+                    .get_top_words(top_words)
+                    .into_iter()
+                    .map(|(_, (word, count))| (word.to_string(), count))
+                    .collect(),
+            });
+        }
+
+        chapters
+    }
+}
+
+/// Words of a section outside its heading lines.
+fn body_words(text: &str) -> usize {
+    text.lines()
+        .filter(|line| !is_heading(line.trim()))
+        .map(|line| tokenize(line).count())
+        .sum()
+}
+
+fn is_heading(line: &str) -> bool {
+    if line.is_empty() || line.chars().count() > MAX_HEADING_LENGTH {
+        return false;
+    }
+
+    let mut words = line.split_whitespace();
+    let first = match words.next() {
+        Some(word) => word.trim_end_matches(['.', ':']),
+        None => return false,
+    };
+    let rest: Vec<&str> = words.collect();
+
+    // "IV", "IV." or "IV. THE RED-HEADED LEAGUE"
+    if roman_numeral_value(first).is_some() {
+        return rest.is_empty()
+            || (line.contains('.')
+                && rest
+                    .iter()
+                    .all(|word| !word.chars().any(char::is_lowercase)));
+    }
+
+    let starts_capitalized = first.chars().next().is_some_and(char::is_uppercase);
+    let keyword = first.to_lowercase();
+    if !starts_capitalized {
+        return false;
+    }
+
+    if UNNUMBERED_HEADINGS.contains(&keyword.as_str()) {
+        return rest.is_empty();
+    }
+
+    if HEADING_KEYWORDS.contains(&keyword.as_str()) {
+        if let Some(number) = rest.first() {
+            let number = number.trim_end_matches(['.', ':', ',']);
+            return roman_numeral_value(number).is_some()
+                || (!number.is_empty() && number.chars().all(|ch| ch.is_ascii_digit()))
+                || NUMBER_WORDS.contains(&number.to_lowercase().as_str());
+        }
+    }
+
+    false
+}
+
+/// Parses an upper-case roman numeral, rejecting non-canonical spellings
+/// so that words like "DID" or "MIX" are not taken for numbers.
+fn roman_numeral_value(text: &str) -> Option<u32> {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    if text.is_empty() || text.len() > 12 {
+        return None;
+    }
+
+    let mut rest = text;
+    let mut value = 0;
+    for (numeral_value, numeral) in NUMERALS {
+        while let Some(remaining) = rest.strip_prefix(numeral) {
+            rest = remaining;
+            value += numeral_value;
+        }
+    }
+
+    let mut canonical = String::new();
+    let mut remaining_value = value;
+    for (numeral_value, numeral) in NUMERALS {
+        while remaining_value >= numeral_value {
+            remaining_value -= numeral_value;
+            canonical.push_str(numeral);
+        }
+    }
+
+    // Only values below 100 are plausible chapter numbers; larger ones are
+    // far more likely to be ordinary words ("DIM", "MIX", "CIVIL").
+    if rest.is_empty() && canonical == text && value < 100 {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filler(words: usize) -> String {
+        vec!["lorem"; words].join(" ")
+    }
+
+    #[test]
+    fn test_heading_patterns() {
+        for heading in [
+            "CHAPTER IV",
+            "Chapter 12.",
+            "BOOK II",
+            "Letter 3",
+            "STAVE ONE.",
+            "CHAPTER I. Down the Rabbit-Hole",
+            "XIV.",
+            "VII",
+            "IV. THE BOSCOMBE VALLEY MYSTERY",
+            "EPILOGUE",
+        ] {
+            assert!(is_heading(heading), "{heading}");
+        }
+
+        for line in [
+            "chapter four",
+            "Chapter and verse were quoted.",
+            "I. am not sure, said he",
+            "DID",
+            "MIX",
+            "Book",
+            "",
+        ] {
+            assert!(!is_heading(line), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_roman_numerals() {
+        assert_eq!(roman_numeral_value("XLIV"), Some(44));
+        assert_eq!(roman_numeral_value("IIII"), None);
+        assert_eq!(roman_numeral_value("Iv"), None);
+        assert_eq!(roman_numeral_value("CIVIL"), None);
+    }
+
+    #[test]
+    fn test_split_chapters_skips_contents_and_front_matter() {
+        let content = format!(
+            "ALICE\n\nCONTENTS\n\nCHAPTER I\nCHAPTER II\n\n\
+             CHAPTER I\n\n{}\n\nCHAPTER II\n\n{}\n",
+            filler(30),
+            filler(40)
+        );
+
+        let chapters = split_chapters(&content);
+
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].0, "CHAPTER I");
+        assert!(chapters[0].1.starts_with("CHAPTER I\n\nlorem"));
+        assert_eq!(chapters[1].0, "CHAPTER II");
+        assert_eq!(tokenize(chapters[1].1).count(), 42);
+    }
+
+    #[test]
+    fn test_split_chapters_merges_short_sections() {
+        let content = format!(
+            "PROLOGUE\n\n{}\n\nCHAPTER I\n\n{}\n\nCHAPTER II\n\n{}\n\n\
+             CHAPTER III\n\n{}\n\nEPILOGUE\n\n{}\n",
+            filler(10),
+            filler(30),
+            filler(5),
+            filler(30),
+            filler(3)
+        );
+
+        let chapters = split_chapters(&content);
+
+        let titles: Vec<&str> = chapters.iter().map(|(title, _)| title.as_str()).collect();
+        assert_eq!(titles, vec!["CHAPTER I", "CHAPTER III"]);
+        assert!(chapters[0].1.starts_with("PROLOGUE"));
+        assert!(chapters[1].1.starts_with("CHAPTER II\n"));
+        assert!(chapters[1].1.ends_with("EPILOGUE\n\nlorem lorem lorem\n"));
+        let words: usize = chapters.iter().map(|(_, text)| body_words(text)).sum();
+        assert_eq!(words, 78);
+    }
+
+    #[test]
+    fn test_split_chapters_without_headings() {
+        let chapters = split_chapters("Just one long story.");
+
+        assert_eq!(chapters, vec![(String::new(), "Just one long story.")]);
+        assert!(split_chapters("  \n").is_empty());
+    }
+
+    #[test]
+    fn test_table_of_contents() {
+        let content = format!(
+            "Préface\n\nLetter 1\n\nDear sister, {}\n\nLetter 2\n\nthe sea the sea {}\n",
+            filler(25),
+            filler(25)
+        );
+
+        let chapters = Chapter::table_of_contents(&content, 1);
+
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].start, 9);
+        assert_eq!(&content[10..18], "Letter 1");
+        assert_eq!(chapters[0].end, chapters[1].start);
+        assert_eq!(chapters[1].end, content.chars().count());
+        assert_eq!(chapters[0].word_count, 28);
+        assert_eq!(chapters[1].top_words, vec![("lorem".to_string(), 25)]);
+        assert_eq!(chapters[1].index, 1);
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::segmentation::{paragraphs, sentences};
+use crate::stop_words::is_stopword;
+
+lazy_static! {
+    /// Titles that mark the capitalized words after them as a name, even at
+    /// the start of a sentence.
+    static ref HONORIFICS: HashSet<&'static str> = {
+        let honorifics: [&str; 36] = [
+            "mr", "mrs", "ms", "miss", "mme", "mlle", "madame", "madam", "master", "dr",
+            "doctor", "professor", "prof", "rev", "reverend", "captain", "capt", "colonel",
+            "col", "major", "general", "lieutenant", "lt", "admiral", "sir", "dame", "lady",
+            "lord", "aunt", "uncle", "king", "queen", "prince", "princess", "duke", "duchess",
+        ];
+        honorifics.iter().cloned().collect()
+    };
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Character {
+    /// Position in the cast, used as the node id of the network.
+    pub id: usize,
+    pub name: String,
+    pub mentions: u32,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum CooccurrenceScope {
+    /// Characters named in the same paragraph.
+    #[default]
+    Paragraph,
+    /// Characters named within a number of tokens of each other.
+    Window,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    Graphml,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct CharacterEdge {
+    /// Ids of the two characters, the lower one first.
+    pub source: usize,
+    pub target: usize,
+    /// Paragraphs, or mentions within the window, the two share.
+    pub weight: u32,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct CharacterNetwork {
+    pub scope: CooccurrenceScope,
+    /// Only set for [`CooccurrenceScope::Window`].
+    pub window: Option<usize>,
+    pub nodes: Vec<Character>,
+    pub edges: Vec<CharacterEdge>,
+}
+
+/// A name as it occurs in the text.
+struct Mention {
+    name: String,
+    paragraph: usize,
+    /// Offset of the name's first word among the whitespace-separated
+    /// words of the text that contain a letter. Only distances between
+    /// mentions are used, so this need not match the offsets of
+    /// [`tokenize`](crate::book::tokenize).
+    position: usize,
+    /// Whether the name could be an ordinary word capitalized only because
+    /// it opens a sentence or a quotation.
+    ambiguous: bool,
+}
+
+/// Characters named at least `min_mentions` times, most mentioned first.
+///
+/// Names are runs of capitalized words that are more often capitalized
+/// than not, either following an honorific ("Mr. Darcy", "Captain Ahab")
+/// or found at least once away from the start of a sentence. Different
+/// names of the same character are listed separately.
+pub fn cast(content: &str, min_mentions: u32) -> Vec<Character> {
+    cast_of(&mentions(content), min_mentions)
+}
+
+impl CharacterNetwork {
+    /// Links every two characters of the [`cast`] that are named in the
+    /// same paragraph, or within `window` tokens of each other.
+    pub fn new(content: &str, scope: CooccurrenceScope, window: usize, min_mentions: u32) -> Self {
+        let mentions = mentions(content);
+        let nodes = cast_of(&mentions, min_mentions);
+        let ids: HashMap<&str, usize> = nodes
+            .iter()
+            .map(|character| (character.name.as_str(), character.id))
+            .collect();
+        let located: Vec<(usize, &Mention)> = mentions
+            .iter()
+            .filter_map(|mention| Some((*ids.get(mention.name.as_str())?, mention)))
+            .collect();
+
+        let mut weights: BTreeMap<(usize, usize), u32> = BTreeMap::new();
+        let mut link = |a: usize, b: usize| {
+            *weights.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        };
+        match scope {
+            CooccurrenceScope::Paragraph => {
+                let mut present: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+                for &(id, mention) in &located {
+                    let ids = present.entry(mention.paragraph).or_default();
+                    if !ids.contains(&id) {
+                        ids.push(id);
+                    }
+                }
+                for ids in present.values() {
+                    for (i, &a) in ids.iter().enumerate() {
+                        for &b in &ids[i + 1..] {
+                            link(a, b);
+                        }
+                    }
+                }
+            }
+            CooccurrenceScope::Window => {
+                let mut first = 0;
+                for (j, &(id, mention)) in located.iter().enumerate() {
+                    while mention.position - located[first].1.position > window {
+                        first += 1;
+                    }
+                    let mut others: Vec<usize> = located[first..j]
+                        .iter()
+                        .map(|&(other, _)| other)
+                        .filter(|&other| other != id)
+                        .collect();
+                    others.sort_unstable();
+                    others.dedup();
+                    for other in others {
+                        link(id, other);
+                    }
+                }
+            }
+        }
+
+        let mut edges: Vec<CharacterEdge> = weights
+            .into_iter()
+            .map(|((source, target), weight)| CharacterEdge {
+                source,
+                target,
+                weight,
+            })
+            .collect();
+        edges.sort_by_key(|edge| std::cmp::Reverse(edge.weight));
+
+        Self {
+            scope,
+            window: (scope == CooccurrenceScope::Window).then_some(window),
+            nodes,
+            edges,
+        }
+    }
+
+    /// The network as an undirected GraphML document, with each node's name
+    /// and mentions and each edge's weight as attributes.
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n  \
+             <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n  \
+             <key id=\"mentions\" for=\"node\" attr.name=\"mentions\" attr.type=\"int\"/>\n  \
+             <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n  \
+             <graph id=\"characters\" edgedefault=\"undirected\">\n",
+        );
+        for node in &self.nodes {
+            xml.push_str(&format!(
+                "    <node id=\"n{}\"><data key=\"name\">{}</data>\
+                 <data key=\"mentions\">{}</data></node>\n",
+                node.id,
+                escape_xml(&node.name),
+                node.mentions
+            ));
+        }
+        for edge in &self.edges {
+            xml.push_str(&format!(
+                "    <edge source=\"n{}\" target=\"n{}\"><data key=\"weight\">{}</data></edge>\n",
+                edge.source, edge.target, edge.weight
+            ));
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn cast_of(mentions: &[Mention], min_mentions: u32) -> Vec<Character> {
+    let mut counts: HashMap<&str, (u32, bool)> = HashMap::new();
+    for mention in mentions {
+        let (count, anchored) = counts.entry(&mention.name).or_insert((0, false));
+        *count += 1;
+        *anchored |= !mention.ambiguous;
+    }
+
+    let mut characters: Vec<(&str, u32)> = counts
+        .into_iter()
+        .filter(|&(_, (count, anchored))| anchored && count >= min_mentions)
+        .map(|(name, (count, _))| (name, count))
+        .collect();
+    characters.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    characters
+        .into_iter()
+        .enumerate()
+        .map(|(id, (name, mentions))| Character {
+            id,
+            name: name.to_string(),
+            mentions,
+        })
+        .collect()
+}
+
+/// A word without surrounding punctuation or a possessive "'s".
+fn bare(raw: &str) -> &str {
+    let word = raw.trim_matches(|ch: char| !ch.is_alphabetic());
+    word.strip_suffix("'s")
+        .or_else(|| word.strip_suffix("’s"))
+        .unwrap_or(word)
+}
+
+/// Starts with a capital and goes on in lowercase, ruling out headings,
+/// initials and "I".
+fn is_capitalized(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(char::is_uppercase)
+        && chars.clone().next().is_some()
+        && chars.all(|ch| ch.is_alphabetic() && !ch.is_uppercase())
+}
+
+/// Every candidate name in `content`, in reading order.
+fn mentions(content: &str) -> Vec<Mention> {
+    // Words capitalized less often than not are ordinary words.
+    let mut case_counts: HashMap<String, (u32, u32)> = HashMap::new();
+    for raw in content.split_whitespace() {
+        let word = bare(raw);
+        if word.is_empty() {
+            continue;
+        }
+        let (capitalized, lowercase) = case_counts.entry(word.to_lowercase()).or_default();
+        if is_capitalized(word) {
+            *capitalized += 1;
+        } else if word.chars().all(char::is_lowercase) {
+            *lowercase += 1;
+        }
+    }
+    let is_name_word = |word: &str| {
+        let lower = word.to_lowercase();
+        is_capitalized(word)
+            && !is_stopword(&lower)
+            && case_counts
+                .get(&lower)
+                .is_some_and(|&(capitalized, lowercase)| capitalized > lowercase)
+    };
+
+    let mut mentions = Vec::new();
+    let mut position = 0;
+    for (paragraph, text) in paragraphs(content).iter().enumerate() {
+        for sentence in sentences(text) {
+            let mut name: Vec<&str> = Vec::new();
+            let mut titled = false;
+            let mut ambiguous = false;
+            let mut start = 0;
+            let mut flush = |name: &mut Vec<&str>, titled: bool, ambiguous: bool, start| {
+                if name.len() > usize::from(titled) {
+                    mentions.push(Mention {
+                        name: name.join(" "),
+                        paragraph,
+                        position: start,
+                        ambiguous: ambiguous && !titled,
+                    });
+                }
+                name.clear();
+            };
+
+            for (index, raw) in sentence.split_whitespace().enumerate() {
+                if !raw.chars().any(char::is_alphabetic) {
+                    flush(&mut name, titled, ambiguous, start);
+                    continue;
+                }
+                let word = bare(raw);
+                let opens = index == 0 || raw.starts_with(['"', '“', '‘', '\'', '(', '[', '_']);
+                if opens {
+                    flush(&mut name, titled, ambiguous, start);
+                }
+
+                let honorific = is_capitalized(word) && HONORIFICS.contains(&*word.to_lowercase());
+                if honorific {
+                    flush(&mut name, titled, ambiguous, start);
+                    // Keeps the period of an abbreviated title.
+                    let titled_word = raw.trim_start_matches(|ch: char| !ch.is_alphabetic());
+                    let period = titled_word[word.len()..].starts_with('.');
+                    name.push(&titled_word[..word.len() + usize::from(period)]);
+                    (titled, ambiguous, start) = (true, opens, position);
+                } else if is_name_word(word) {
+                    if name.is_empty() {
+                        (titled, ambiguous, start) = (false, opens, position);
+                    }
+                    name.push(word);
+                } else {
+                    flush(&mut name, titled, ambiguous, start);
+                }
+                position += 1;
+
+                let trimmed = raw.trim_end_matches(|ch: char| !ch.is_alphabetic());
+                if !honorific && (trimmed.len() < raw.len() || !trimmed.ends_with(word)) {
+                    flush(&mut name, titled, ambiguous, start);
+                }
+            }
+            flush(&mut name, titled, ambiguous, start);
+        }
+    }
+
+    // "Later Queequeg" opening a sentence is Queequeg, if Queequeg is named
+    // elsewhere.
+    let anchored: HashSet<String> = mentions
+        .iter()
+        .filter(|mention| !mention.ambiguous)
+        .map(|mention| mention.name.clone())
+        .collect();
+    for mention in &mut mentions {
+        if mention.ambiguous && !anchored.contains(&mention.name) {
+            if let Some((_, rest)) = mention.name.split_once(' ') {
+                if anchored.contains(rest) {
+                    mention.name = rest.to_string();
+                    mention.position += 1;
+                }
+            }
+        }
+    }
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+Captain Ahab stood on the deck. The crew feared Ahab, and Starbuck
+watched him in silence.
+
+Ishmael talked with Queequeg. Later Queequeg and Ishmael ate chowder
+with Mrs. Hussey.
+
+Ahab's leg ached. \"Starbuck,\" he said, \"the whale waits.\" Then the
+Whale rose and Starbuck shouted for Ahab.";
+
+    fn names(cast: &[Character]) -> Vec<(&str, u32)> {
+        cast.iter()
+            .map(|character| (character.name.as_str(), character.mentions))
+            .collect()
+    }
+
+    #[test]
+    fn test_cast() {
+        let cast = cast(TEXT, 1);
+
+        // "The", "Then" and "Later" only open sentences, "Whale" is more
+        // often lowercase, and "Captain Ahab" keeps its title.
+        assert_eq!(
+            names(&cast),
+            vec![
+                ("Ahab", 3),
+                ("Starbuck", 3),
+                ("Ishmael", 2),
+                ("Queequeg", 2),
+                ("Captain Ahab", 1),
+                ("Mrs. Hussey", 1),
+            ]
+        );
+        assert_eq!(
+            names(&super::cast(TEXT, 3)),
+            vec![("Ahab", 3), ("Starbuck", 3)]
+        );
+    }
+
+    #[test]
+    fn test_network_by_paragraph() {
+        let network = CharacterNetwork::new(TEXT, CooccurrenceScope::Paragraph, 0, 2);
+
+        assert_eq!(network.window, None);
+        let edges: Vec<(usize, usize, u32)> = network
+            .edges
+            .iter()
+            .map(|edge| (edge.source, edge.target, edge.weight))
+            .collect();
+        // Ahab and Starbuck share two paragraphs, Ishmael and Queequeg one.
+        assert_eq!(edges, vec![(0, 1, 2), (2, 3, 1)]);
+    }
+
+    #[test]
+    fn test_network_by_window() {
+        let network = CharacterNetwork::new(TEXT, CooccurrenceScope::Window, 3, 2);
+
+        assert_eq!(network.window, Some(3));
+        let pairs: Vec<(usize, usize)> = network
+            .edges
+            .iter()
+            .map(|edge| (edge.source, edge.target))
+            .collect();
+        assert!(pairs.contains(&(2, 3)));
+        assert!(!pairs.contains(&(0, 2)));
+    }
+
+    #[test]
+    fn test_graphml() {
+        let network = CharacterNetwork {
+            scope: CooccurrenceScope::Paragraph,
+            window: None,
+            nodes: vec![
+                Character {
+                    id: 0,
+                    name: "Mr. <Darcy> & Co".to_string(),
+                    mentions: 4,
+                },
+                Character {
+                    id: 1,
+                    name: "Elizabeth".to_string(),
+                    mentions: 3,
+                },
+            ],
+            edges: vec![CharacterEdge {
+                source: 0,
+                target: 1,
+                weight: 2,
+            }],
+        };
+
+        let xml = network.to_graphml();
+
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("<data key=\"name\">Mr. &lt;Darcy&gt; &amp; Co</data>"));
+        assert!(
+            xml.contains("<edge source=\"n0\" target=\"n1\"><data key=\"weight\">2</data></edge>")
+        );
+        assert!(xml.trim_end().ends_with("</graphml>"));
+    }
+}
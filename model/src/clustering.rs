@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::keyness::CorpusFrequencies;
+use crate::stop_words::is_stopword;
+
+/// Keywords that make up a generated shelf's name.
+const NAME_KEYWORDS: usize = 3;
+
+/// A shelf made by clustering books on their content, as opposed to the
+/// curated [`Bookshelf`](crate::book::Bookshelf).
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct AutoShelf {
+    pub shelf_id: i32,
+    pub shelf_name: String,
+    pub keywords: Vec<String>,
+    pub count_of_books: i32,
+    /// Always true, so clients can tell these from curated shelves.
+    pub generated: bool,
+}
+
+impl AutoShelf {
+    /// Names the shelf after its first keywords.
+    pub fn new(shelf_id: i32, keywords: Vec<String>, count_of_books: i32) -> Self {
+        let shelf_name = keywords
+            .iter()
+            .take(NAME_KEYWORDS)
+            .map(|keyword| capitalize(keyword))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Self {
+            shelf_id,
+            shelf_name,
+            keywords,
+            count_of_books,
+            generated: true,
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub struct Clusters {
+    /// Cluster of each vector.
+    pub assignments: Vec<usize>,
+    /// Cosine similarity of each vector to its cluster's centroid.
+    pub similarities: Vec<f32>,
+}
+
+/// Spherical k-means on unit-length vectors: clusters by cosine similarity
+/// and keeps the centroids at unit length. The first centroids are picked
+/// farthest-first from the first vector, so the result is reproducible.
+pub fn spherical_k_means(vectors: &[&[f32]], k: usize, iterations: usize) -> Clusters {
+    let k = k.min(vectors.len());
+    if k == 0 {
+        return Clusters {
+            assignments: Vec::new(),
+            similarities: Vec::new(),
+        };
+    }
+
+    let mut centroids: Vec<Vec<f32>> = vec![vectors[0].to_vec()];
+    let mut closest: Vec<f32> = vectors.iter().map(|v| dot(v, &centroids[0])).collect();
+    while centroids.len() < k {
+        let (farthest, _) = closest
+            .iter()
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+        centroids.push(vectors[farthest].to_vec());
+        let centroid = centroids.last().unwrap();
+        for (similarity, vector) in closest.iter_mut().zip(vectors) {
+            *similarity = similarity.max(dot(vector, centroid));
+        }
+    }
+
+    let mut assignments = vec![usize::MAX; vectors.len()];
+    let mut similarities = vec![0.0; vectors.len()];
+    for _ in 0..iterations.max(1) {
+        let mut changed = false;
+        for (i, vector) in vectors.iter().enumerate() {
+            let (cluster, similarity) = centroids
+                .iter()
+                .map(|centroid| dot(vector, centroid))
+                .enumerate()
+                .fold((0, f32::NEG_INFINITY), |best, (cluster, similarity)| {
+                    if similarity > best.1 {
+                        (cluster, similarity)
+                    } else {
+                        best
+                    }
+                });
+            changed |= assignments[i] != cluster;
+            assignments[i] = cluster;
+            similarities[i] = similarity;
+        }
+        if !changed {
+            break;
+        }
+
+        let dimensions = vectors[0].len();
+        let mut sums = vec![vec![0.0f32; dimensions]; k];
+        for (vector, &cluster) in vectors.iter().zip(&assignments) {
+            for (sum, x) in sums[cluster].iter_mut().zip(vector.iter()) {
+                *sum += x;
+            }
+        }
+        for (centroid, sum) in centroids.iter_mut().zip(sums) {
+            let norm = sum.iter().map(|x| x * x).sum::<f32>().sqrt();
+            // An emptied cluster keeps its old centroid.
+            if norm > 0.0 {
+                *centroid = sum.into_iter().map(|x| x / norm).collect();
+            }
+        }
+    }
+
+    Clusters {
+        assignments,
+        similarities,
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// The `limit` words that best characterize a cluster: used by many of its
+/// `members` but by few books of the whole `corpus`. `member_frequencies`
+/// counts how many members use each word.
+pub fn cluster_keywords(
+    member_frequencies: &HashMap<String, u32>,
+    members: u32,
+    corpus: &CorpusFrequencies,
+    limit: usize,
+) -> Vec<String> {
+    let documents = corpus.document_count.max(members) as f64;
+    let mut scored: Vec<(&String, f64)> = member_frequencies
+        .iter()
+        .filter(|(word, _)| !is_stopword(word))
+        .map(|(word, &count)| {
+            let document_frequency = corpus
+                .document_frequencies
+                .get(word)
+                .copied()
+                .unwrap_or(0)
+                .max(count);
+            let idf = (documents / document_frequency as f64).ln();
+            (word, count as f64 / members.max(1) as f64 * idf)
+        })
+        .filter(|&(_, score)| score > 0.0)
+        .collect();
+
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(word, _)| word.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(x: f32, y: f32) -> Vec<f32> {
+        let norm = (x * x + y * y).sqrt();
+        vec![x / norm, y / norm]
+    }
+
+    #[test]
+    fn test_k_means_groups_by_direction() {
+        let points = [
+            unit(1.0, 0.1),
+            unit(0.1, 1.0),
+            unit(1.0, 0.0),
+            unit(0.0, 1.0),
+            unit(1.0, 0.2),
+        ];
+        let vectors: Vec<&[f32]> = points.iter().map(Vec::as_slice).collect();
+
+        let clusters = spherical_k_means(&vectors, 2, 20);
+
+        let a = clusters.assignments[0];
+        assert_eq!(clusters.assignments, vec![a, 1 - a, a, 1 - a, a]);
+        assert!(clusters.similarities.iter().all(|&s| s > 0.9));
+        assert_eq!(spherical_k_means(&vectors, 9, 20).assignments.len(), 5);
+        assert!(spherical_k_means(&[], 3, 20).assignments.is_empty());
+    }
+
+    #[test]
+    fn test_cluster_keywords() {
+        let corpus = CorpusFrequencies {
+            document_count: 100,
+            document_frequencies: [("whale", 4), ("sea", 40), ("ship", 10), ("the", 100)]
+                .iter()
+                .map(|&(word, df)| (word.to_string(), df))
+                .collect(),
+            ..Default::default()
+        };
+        let members: HashMap<String, u32> = [("whale", 4), ("sea", 5), ("ship", 1), ("the", 5)]
+            .iter()
+            .map(|&(word, count)| (word.to_string(), count))
+            .collect();
+
+        let keywords = cluster_keywords(&members, 5, &corpus, 2);
+
+        assert_eq!(keywords, vec!["whale", "sea"]);
+        let shelf = AutoShelf::new(1, keywords, 5);
+        assert_eq!(shelf.shelf_name, "Whale, Sea");
+        assert!(shelf.generated);
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stop_words::is_stopword;
+
+/// Words seen fewer times than this in both books together are left out of
+/// the log-ratio rankings, where a single occurrence would look extreme.
+const MIN_LOG_RATIO_COUNT: u32 = 5;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct WordComparison {
+    pub word: String,
+    pub count_a: u32,
+    pub count_b: u32,
+    /// Binary log of the word's relative frequency in A over that in B:
+    /// 1 means twice as common in A, -1 twice as common in B.
+    pub log_ratio: f64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Comparison {
+    /// Share of the two vocabularies the books have in common.
+    pub jaccard: f64,
+    /// Cosine similarity of the two word frequency vectors.
+    pub cosine: f64,
+    /// Most frequent words used by both books.
+    pub shared_words: Vec<WordComparison>,
+    /// Most frequent words of each book the other never uses.
+    pub only_in_a: Vec<WordComparison>,
+    pub only_in_b: Vec<WordComparison>,
+    /// Words most over-represented in each book relative to the other.
+    pub more_in_a: Vec<WordComparison>,
+    pub more_in_b: Vec<WordComparison>,
+}
+
+impl Comparison {
+    /// Compares the vocabularies of two books, keeping `limit` words in each
+    /// list. Stopwords count towards the similarities but are not listed.
+    pub fn new(a: &HashMap<String, u32>, b: &HashMap<String, u32>, limit: usize) -> Self {
+        let total_a: u64 = a.values().map(|&count| count as u64).sum();
+        let total_b: u64 = b.values().map(|&count| count as u64).sum();
+
+        let words: HashSet<&String> = a.keys().chain(b.keys()).collect();
+        let comparisons: Vec<WordComparison> = words
+            .into_iter()
+            .filter(|word| !is_stopword(word))
+            .map(|word| {
+                let count_a = a.get(word).copied().unwrap_or(0);
+                let count_b = b.get(word).copied().unwrap_or(0);
+                WordComparison {
+                    word: word.clone(),
+                    count_a,
+                    count_b,
+                    log_ratio: log_ratio(count_a, total_a, count_b, total_b),
+                }
+            })
+            .collect();
+
+        let relative = |word: &WordComparison| {
+            word.count_a as f64 / total_a.max(1) as f64
+                + word.count_b as f64 / total_b.max(1) as f64
+        };
+        let top = |mut words: Vec<WordComparison>, key: &dyn Fn(&WordComparison) -> f64| {
+            words.sort_by(|x, y| key(y).total_cmp(&key(x)).then_with(|| x.word.cmp(&y.word)));
+            words.truncate(limit);
+            words
+        };
+
+        let shared = comparisons
+            .iter()
+            .filter(|word| word.count_a > 0 && word.count_b > 0)
+            .cloned()
+            .collect();
+        let only_a = comparisons
+            .iter()
+            .filter(|word| word.count_b == 0)
+            .cloned()
+            .collect();
+        let only_b = comparisons
+            .iter()
+            .filter(|word| word.count_a == 0)
+            .cloned()
+            .collect();
+        let frequent: Vec<WordComparison> = comparisons
+            .into_iter()
+            .filter(|word| word.count_a + word.count_b >= MIN_LOG_RATIO_COUNT)
+            .collect();
+
+        Self {
+            jaccard: jaccard(a, b),
+            cosine: cosine(a, b),
+            shared_words: top(shared, &relative),
+            only_in_a: top(only_a, &|word| word.count_a as f64),
+            only_in_b: top(only_b, &|word| word.count_b as f64),
+            more_in_a: top(
+                frequent
+                    .iter()
+                    .filter(|w| w.log_ratio > 0.0)
+                    .cloned()
+                    .collect(),
+                &|word| word.log_ratio,
+            ),
+            more_in_b: top(
+                frequent.into_iter().filter(|w| w.log_ratio < 0.0).collect(),
+                &|word| -word.log_ratio,
+            ),
+        }
+    }
+}
+
+/// Size of the intersection of two vocabularies over that of their union.
+pub fn jaccard(a: &HashMap<String, u32>, b: &HashMap<String, u32>) -> f64 {
+    let shared = a.keys().filter(|word| b.contains_key(*word)).count();
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        0.0
+    } else {
+        shared as f64 / union as f64
+    }
+}
+
+/// Cosine of the angle between two sparse vectors, such as word counts or
+/// weights keyed by word.
+pub fn cosine<K, V>(a: &HashMap<K, V>, b: &HashMap<K, V>) -> f64
+where
+    K: Eq + Hash,
+    V: Copy + Into<f64>,
+{
+    let norm = |vector: &HashMap<K, V>| {
+        vector
+            .values()
+            .map(|&value| value.into() * value.into())
+            .sum::<f64>()
+            .sqrt()
+    };
+    let (norm_a, norm_b) = (norm(a), norm(b));
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(key, &value)| b.get(key).map(|&other| value.into() * other.into()))
+        // Folding from 0.0: an empty `sum` of floats is -0.0.
+        .fold(0.0, |dot, product| dot + product);
+    dot / (norm_a * norm_b)
+}
+
+/// Hardie's log ratio, with half a count added to each side so that words
+/// missing from one book get a finite value.
+fn log_ratio(count_a: u32, total_a: u64, count_b: u32, total_b: u64) -> f64 {
+    if total_a == 0 || total_b == 0 {
+        return 0.0;
+    }
+    let relative_a = (count_a as f64 + 0.5) / total_a as f64;
+    let relative_b = (count_b as f64 + 0.5) / total_b as f64;
+    (relative_a / relative_b).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::count_words;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_similarities() {
+        let a = count_words("whale whale sea");
+        let b = count_words("sea sea ship");
+
+        assert_close(jaccard(&a, &b), 1.0 / 3.0);
+        // (2*0 + 1*2 + 0*1) / (sqrt(5) * sqrt(5))
+        assert_close(cosine(&a, &b), 0.4);
+        assert_close(cosine(&a, &a), 1.0);
+        assert_eq!(cosine(&a, &HashMap::new()), 0.0);
+        assert_eq!(jaccard(&HashMap::new(), &HashMap::new()), 0.0);
+    }
+
+    #[test]
+    fn test_word_lists() {
+        let a = count_words("the whale and the whale and the sea ahab ahab ahab ahab ahab");
+        let b = count_words("the sea and the ship and the sea ishmael");
+
+        let comparison = Comparison::new(&a, &b, 10);
+
+        let words = |list: &[WordComparison]| -> Vec<String> {
+            list.iter().map(|word| word.word.clone()).collect()
+        };
+        assert_eq!(words(&comparison.shared_words), vec!["sea"]);
+        assert_eq!(words(&comparison.only_in_a), vec!["ahab", "whale"]);
+        assert_eq!(words(&comparison.only_in_b), vec!["ishmael", "ship"]);
+        assert_eq!(words(&comparison.more_in_a), vec!["ahab"]);
+        assert!(comparison.more_in_b.is_empty());
+        assert_close(
+            comparison.shared_words[0].log_ratio,
+            (1.5f64 / 13.0 / (2.5 / 9.0)).log2(),
+        );
+    }
+}
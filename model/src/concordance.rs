@@ -0,0 +1,157 @@
+use lazy_static::lazy_static;
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+
+use crate::book::{token_spans, tokenize};
+
+lazy_static! {
+    static ref STEMMER: Stemmer = Stemmer::create(Algorithm::English);
+}
+
+/// Reduces a token to its Snowball (Porter 2) English stem.
+pub fn stem(token: &str) -> String {
+    STEMMER.stem(token).into_owned()
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct ConcordanceLine {
+    /// Character (not byte) offset of the match in the text.
+    pub offset: usize,
+    pub left: String,
+    pub matched: String,
+    pub right: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Concordance {
+    pub query: String,
+    pub total_matches: usize,
+    pub lines: Vec<ConcordanceLine>,
+}
+
+impl Concordance {
+    /// Finds every occurrence of `query` in `content`, ignoring case and
+    /// punctuation, and returns up to `limit` of them with `window` words of
+    /// context on each side. A query of several words is matched as a
+    /// phrase; with `stemmed` set, "walk" also finds "walked" and "walking".
+    pub fn new(content: &str, query: &str, window: usize, limit: usize, stemmed: bool) -> Self {
+        let normalize = |token: &str| {
+            if stemmed {
+                stem(token)
+            } else {
+                token.to_string()
+            }
+        };
+
+        let terms: Vec<String> = tokenize(query).map(|term| normalize(&term)).collect();
+        let spans = token_spans(content);
+        let keys: Vec<String> = spans.iter().map(|span| normalize(&span.token)).collect();
+
+        let mut total_matches = 0;
+        let mut lines = Vec::new();
+        let mut chars_before = 0;
+        let mut counted_until = 0;
+
+        if !terms.is_empty() {
+            for (index, candidate) in keys.windows(terms.len()).enumerate() {
+                if candidate != terms.as_slice() {
+                    continue;
+                }
+                total_matches += 1;
+                if lines.len() >= limit {
+                    continue;
+                }
+
+                let first = &spans[index];
+                let last = &spans[index + terms.len() - 1];
+                chars_before += content[counted_until..first.start].chars().count();
+                counted_until = first.start;
+
+                let left_start = spans[index.saturating_sub(window)].start;
+                let right_end = spans[(index + terms.len() - 1 + window).min(spans.len() - 1)].end;
+
+                lines.push(ConcordanceLine {
+                    offset: chars_before,
+                    left: collapse_whitespace(&content[left_start..first.start]),
+                    matched: collapse_whitespace(&content[first.start..last.end]),
+                    right: collapse_whitespace(&content[last.end..right_end]),
+                });
+            }
+        }
+
+        Self {
+            query: query.to_string(),
+            total_matches,
+            lines,
+        }
+    }
+}
+
+/// Joins the words of a slice with single spaces, undoing Gutenberg's
+/// hard line wrapping inside a context window.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "It is a truth universally acknowledged, that a single man\n\
+                        in possession of a good fortune, must be in want of a wife.\n\
+                        A Single man walked; single men walk.";
+
+    #[test]
+    fn test_word_query_is_case_insensitive() {
+        let concordance = Concordance::new(TEXT, "SINGLE", 2, 10, false);
+
+        assert_eq!(concordance.total_matches, 3);
+        assert_eq!(
+            concordance.lines[0],
+            ConcordanceLine {
+                offset: 47,
+                left: "that a".to_string(),
+                matched: "single".to_string(),
+                right: "man in".to_string(),
+            }
+        );
+        assert_eq!(&TEXT[47..53], "single");
+        assert_eq!(concordance.lines[1].matched, "Single");
+    }
+
+    #[test]
+    fn test_phrase_query() {
+        let concordance = Concordance::new(TEXT, "single man", 1, 10, false);
+
+        assert_eq!(concordance.total_matches, 2);
+        assert_eq!(concordance.lines[0].left, "a");
+        assert_eq!(concordance.lines[0].matched, "single man");
+        assert_eq!(concordance.lines[1].right, "walked;");
+    }
+
+    #[test]
+    fn test_stem_query() {
+        let exact = Concordance::new(TEXT, "walk", 0, 10, false);
+        let stemmed = Concordance::new(TEXT, "walking", 0, 10, true);
+
+        assert_eq!(exact.total_matches, 1);
+        assert_eq!(stemmed.total_matches, 2);
+        assert_eq!(stemmed.lines[0].matched, "walked;");
+    }
+
+    #[test]
+    fn test_limit_keeps_total() {
+        let concordance = Concordance::new(TEXT, "a", 3, 2, false);
+
+        assert_eq!(concordance.total_matches, 5);
+        assert_eq!(concordance.lines.len(), 2);
+    }
+
+    #[test]
+    fn test_offsets_count_characters() {
+        let concordance = Concordance::new("Café — café, CAFÉ", "café", 0, 10, false);
+
+        let offsets: Vec<usize> = concordance.lines.iter().map(|line| line.offset).collect();
+        assert_eq!(offsets, vec![0, 7, 13]);
+    }
+}
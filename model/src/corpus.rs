@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::book::{self, Analytics};
+use crate::readability::Readability;
+use crate::richness::LexicalRichness;
+
+/// Corpus-level profile of a group of books, such as a subject, bookshelf
+/// or author.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct CorpusProfile {
+    /// Books the profile was computed from, out of `total_books` members.
+    pub book_count: usize,
+    pub total_books: usize,
+    pub total_words: u64,
+    pub vocabulary_size: usize,
+    pub top_words: Vec<(String, u32)>,
+    /// Means over the books, each book weighing the same.
+    pub average_readability: Readability,
+    pub average_richness: LexicalRichness,
+}
+
+impl CorpusProfile {
+    /// Adds up the word counts of every book of `books`, out of a group of
+    /// `total_books`, and keeps the `top_words` most frequent words of the
+    /// combined text.
+    pub fn new(books: &[Analytics], total_books: usize, top_words: u32) -> Self {
+        if books.is_empty() {
+            return Self {
+                total_books,
+                ..Self::default()
+            };
+        }
+
+        let mut word_map: HashMap<String, u32> = HashMap::new();
+        for analytics in books {
+            for (word, &count) in &analytics.word_map {
+                *word_map.entry(word.clone()).or_insert(0) += count;
+            }
+        }
+
+        let readability: Vec<Readability> = books.iter().map(|a| a.readability.clone()).collect();
+        let richness: Vec<LexicalRichness> = books.iter().map(|a| a.richness.clone()).collect();
+
+        Self {
+            book_count: books.len(),
+            total_books,
+            total_words: word_map.values().map(|&count| count as u64).sum(),
+            vocabulary_size: word_map.len(),
+            top_words: book::top_words(&word_map, top_words)
+                .into_iter()
+                .map(|(word, count)| (word.to_string(), count))
+                .collect(),
+            average_readability: Readability::average(&readability),
+            average_richness: LexicalRichness::average(&richness),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_of_two_books() {
+        let books = [
+            Analytics::new("The whale swam. The whale dove deep."),
+            Analytics::new("A ship sailed. The whale followed the ship."),
+        ];
+
+        let profile = CorpusProfile::new(&books, 3, 2);
+
+        assert_eq!(profile.book_count, 2);
+        assert_eq!(profile.total_books, 3);
+        assert_eq!(profile.total_words, 15);
+        assert_eq!(
+            profile.top_words,
+            vec![("whale".to_string(), 3), ("ship".to_string(), 2)]
+        );
+        assert_eq!(
+            profile.average_readability.flesch_reading_ease,
+            (books[0].readability.flesch_reading_ease + books[1].readability.flesch_reading_ease)
+                / 2.0
+        );
+    }
+
+    #[test]
+    fn test_empty_profile() {
+        assert_eq!(
+            CorpusProfile::new(&[], 4, 10),
+            CorpusProfile {
+                total_books: 4,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+use crate::book::tokenize;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct WordDispersion {
+    pub word: String,
+    pub frequency: u32,
+    /// Token offsets of every occurrence, for lexical dispersion plots.
+    pub positions: Vec<usize>,
+    /// Occurrences falling into each of the equally sized segments.
+    pub segment_counts: Vec<u32>,
+    /// Juilland's D: 1 for a perfectly even spread, 0 for all in one segment.
+    pub juillands_d: f64,
+    /// Gries' DP: 0 for a perfectly even spread, approaching 1 when clumped.
+    pub gries_dp: f64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Dispersion {
+    pub total_tokens: usize,
+    pub segments: usize,
+    pub words: Vec<WordDispersion>,
+}
+
+impl Dispersion {
+    /// Locates each of `words` (a word or a phrase) across `content` and
+    /// measures how evenly it is spread over `segments` equal parts.
+    pub fn new(content: &str, words: &[String], segments: usize) -> Self {
+        let tokens: Vec<String> = tokenize(content).collect();
+        let segments = segments.clamp(1, tokens.len().max(1));
+
+        let segment_sizes: Vec<f64> = (0..segments)
+            .map(|segment| {
+                let start = segment_start(segment, segments, tokens.len());
+                let end = segment_start(segment + 1, segments, tokens.len());
+                (end - start) as f64
+            })
+            .collect();
+
+        let words = words
+            .iter()
+            .map(|word| {
+                let terms: Vec<String> = tokenize(word).collect();
+                let positions: Vec<usize> = if terms.is_empty() {
+                    Vec::new()
+                } else {
+                    tokens
+                        .windows(terms.len())
+                        .enumerate()
+                        .filter(|(_, window)| *window == terms.as_slice())
+                        .map(|(index, _)| index)
+                        .collect()
+                };
+
+                let mut segment_counts = vec![0; segments];
+                for &position in &positions {
+                    segment_counts[position * segments / tokens.len()] += 1;
+                }
+
+                WordDispersion {
+                    word: word.clone(),
+                    frequency: positions.len() as u32,
+                    juillands_d: juillands_d(&segment_counts, &segment_sizes),
+                    gries_dp: gries_dp(&segment_counts, &segment_sizes),
+                    positions,
+                    segment_counts,
+                }
+            })
+            .collect();
+
+        Self {
+            total_tokens: tokens.len(),
+            segments,
+            words,
+        }
+    }
+}
+
+/// First token index of `segment` when `total` tokens are split into
+/// `segments` parts whose sizes differ by at most one. Token `p` falls into
+/// segment `p * segments / total`.
+pub(crate) fn segment_start(segment: usize, segments: usize, total: usize) -> usize {
+    (segment * total).div_ceil(segments)
+}
+
+/// Juilland's D over the relative frequencies of each segment.
+fn juillands_d(counts: &[u32], sizes: &[f64]) -> f64 {
+    let n = counts.len() as f64;
+    if counts.len() < 2 || counts.iter().all(|&count| count == 0) {
+        return 0.0;
+    }
+
+    let frequencies: Vec<f64> = counts
+        .iter()
+        .zip(sizes)
+        .map(|(&count, &size)| if size > 0.0 { count as f64 / size } else { 0.0 })
+        .collect();
+    let mean = frequencies.iter().sum::<f64>() / n;
+    let variance = frequencies
+        .iter()
+        .map(|f| (f - mean) * (f - mean))
+        .sum::<f64>()
+        / n;
+
+    1.0 - (variance.sqrt() / mean) / (n - 1.0).sqrt()
+}
+
+/// Gries' deviation of proportions between where the word occurs and how
+/// large each segment is.
+fn gries_dp(counts: &[u32], sizes: &[f64]) -> f64 {
+    let total_count: u32 = counts.iter().sum();
+    let total_size: f64 = sizes.iter().sum();
+    if total_count == 0 || total_size == 0.0 {
+        return 0.0;
+    }
+
+    counts
+        .iter()
+        .zip(sizes)
+        .map(|(&count, &size)| (count as f64 / total_count as f64 - size / total_size).abs())
+        .sum::<f64>()
+        / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn words(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_even_and_clumped_words() {
+        let content = "y y a b c x d e f g x h i j k l";
+        let dispersion = Dispersion::new(content, &words(&["x", "y"]), 2);
+
+        assert_eq!(dispersion.total_tokens, 16);
+        let even = &dispersion.words[0];
+        assert_eq!(even.positions, vec![5, 10]);
+        assert_eq!(even.segment_counts, vec![1, 1]);
+        assert_close(even.juillands_d, 1.0);
+        assert_close(even.gries_dp, 0.0);
+
+        let clumped = &dispersion.words[1];
+        assert_eq!(clumped.segment_counts, vec![2, 0]);
+        assert_close(clumped.juillands_d, 0.0);
+        assert_close(clumped.gries_dp, 0.5);
+    }
+
+    #[test]
+    fn test_phrases_and_missing_words() {
+        let content = "the white whale rose. Later the white whale dove.";
+        let dispersion = Dispersion::new(content, &words(&["White Whale", "ahab"]), 3);
+
+        assert_eq!(dispersion.words[0].frequency, 2);
+        assert_eq!(dispersion.words[0].positions, vec![1, 6]);
+        assert_eq!(dispersion.words[1].frequency, 0);
+        assert_close(dispersion.words[1].juillands_d, 0.0);
+        assert_close(dispersion.words[1].gries_dp, 0.0);
+    }
+
+    #[test]
+    fn test_segment_bounds() {
+        let sizes: Vec<usize> = (0..3)
+            .map(|segment| segment_start(segment + 1, 3, 10) - segment_start(segment, 3, 10))
+            .collect();
+
+        assert_eq!(sizes.iter().sum::<usize>(), 10);
+        assert!(sizes.iter().all(|&size| size == 3 || size == 4));
+    }
+
+    #[test]
+    fn test_more_segments_than_tokens() {
+        let dispersion = Dispersion::new("one two", &words(&["two"]), 10);
+
+        assert_eq!(dispersion.segments, 2);
+        assert_eq!(dispersion.words[0].segment_counts, vec![0, 1]);
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Number of samples taken along the vocabulary-growth curve.
+const GROWTH_SAMPLES: usize = 100;
+/// Ratio between consecutive ranks in the plotted rank-frequency series, so
+/// that every decade of ranks gets roughly the same number of points.
+const RANK_STEP: f64 = 1.15;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Fit of `frequency = C * rank^-exponent`. `rank_frequency` holds
+/// log-spaced (rank, frequency) points for plotting on log-log axes.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct ZipfFit {
+    pub exponent: f64,
+    pub constant: f64,
+    pub r_squared: f64,
+    pub rank_frequency: Vec<Point>,
+}
+
+/// Fit of `distinct words = K * tokens^beta`. `vocabulary_growth` holds the
+/// sampled (tokens read, distinct words) curve the fit was made on.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct HeapsFit {
+    pub k: f64,
+    pub beta: f64,
+    pub r_squared: f64,
+    pub vocabulary_growth: Vec<Point>,
+}
+
+impl ZipfFit {
+    /// Fits Zipf's law over the whole frequency table. Stopwords are kept:
+    /// they are the head of the distribution the law describes.
+    pub fn new(word_map: &HashMap<String, u32>) -> Self {
+        let mut frequencies: Vec<u32> = word_map.values().cloned().collect();
+        frequencies.sort_unstable_by(|a, b| b.cmp(a));
+
+        let log_points: Vec<(f64, f64)> = frequencies
+            .iter()
+            .enumerate()
+            .map(|(index, &frequency)| (((index + 1) as f64).ln(), (frequency as f64).ln()))
+            .collect();
+        let (slope, intercept, r_squared) = match linear_fit(&log_points) {
+            Some(fit) => fit,
+            None => return Self::default(),
+        };
+
+        let mut rank_frequency = Vec::new();
+        let mut rank = 1;
+        while rank <= frequencies.len() {
+            rank_frequency.push(Point {
+                x: rank as f64,
+                y: frequencies[rank - 1] as f64,
+            });
+            rank = ((rank as f64 * RANK_STEP) as usize).max(rank + 1);
+        }
+
+        Self {
+            exponent: -slope,
+            constant: intercept.exp(),
+            r_squared,
+            rank_frequency,
+        }
+    }
+}
+
+impl HeapsFit {
+    /// Samples the vocabulary growth of a text given as interned `ids`
+    /// below `types`, and fits Heaps' law to it.
+    pub fn from_ids(ids: &[u32], types: usize) -> Self {
+        let interval = (ids.len() / GROWTH_SAMPLES).max(1);
+        let mut seen = vec![false; types];
+        let mut distinct = 0;
+        let mut vocabulary_growth = Vec::new();
+
+        for (index, &id) in ids.iter().enumerate() {
+            if !seen[id as usize] {
+                seen[id as usize] = true;
+                distinct += 1;
+            }
+            let read = index + 1;
+            if read % interval == 0 || read == ids.len() {
+                vocabulary_growth.push(Point {
+                    x: read as f64,
+                    y: distinct as f64,
+                });
+            }
+        }
+
+        Self::from_growth(vocabulary_growth)
+    }
+
+    /// Extends the growth curve with `other`'s text read after this one,
+    /// `vocabulary` being the number of distinct words in both together.
+    /// How many of `other`'s words are new is only known at the end, so its
+    /// curve is scaled to arrive there.
+    pub fn merge(&mut self, other: &Self, vocabulary: usize) {
+        let (read, seen) = self
+            .vocabulary_growth
+            .last()
+            .map_or((0.0, 0.0), |point| (point.x, point.y));
+        let other_seen = other.vocabulary_growth.last().map_or(0.0, |point| point.y);
+        let scale = if other_seen > 0.0 {
+            (vocabulary as f64 - seen) / other_seen
+        } else {
+            0.0
+        };
+
+        let mut growth = std::mem::take(&mut self.vocabulary_growth);
+        growth.extend(other.vocabulary_growth.iter().map(|point| Point {
+            x: read + point.x,
+            y: seen + point.y * scale,
+        }));
+
+        // Thin the curve back out so that repeated merges stay small.
+        let interval = (growth.len() / GROWTH_SAMPLES).max(1);
+        let last = growth.len().saturating_sub(1);
+        let growth = growth
+            .into_iter()
+            .enumerate()
+            .filter(|&(index, _)| (index + 1) % interval == 0 || index == last)
+            .map(|(_, point)| point)
+            .collect();
+
+        *self = Self::from_growth(growth);
+    }
+
+    fn from_growth(vocabulary_growth: Vec<Point>) -> Self {
+        let log_points: Vec<(f64, f64)> = vocabulary_growth
+            .iter()
+            .map(|point| (point.x.ln(), point.y.ln()))
+            .collect();
+        let (beta, intercept, r_squared) = match linear_fit(&log_points) {
+            Some(fit) => fit,
+            None => {
+                return Self {
+                    vocabulary_growth,
+                    ..Self::default()
+                }
+            }
+        };
+
+        Self {
+            k: intercept.exp(),
+            beta,
+            r_squared,
+            vocabulary_growth,
+        }
+    }
+}
+
+/// Ordinary least squares fit of `y = slope * x + intercept`, returning
+/// `(slope, intercept, r_squared)`, or `None` with fewer than two distinct x.
+fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for &(x, y) in points {
+        sxx += (x - mean_x) * (x - mean_x);
+        sxy += (x - mean_x) * (y - mean_y);
+        syy += (y - mean_y) * (y - mean_y);
+    }
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let r_squared = if syy == 0.0 {
+        1.0
+    } else {
+        sxy * sxy / (sxx * syy)
+    };
+
+    Some((slope, intercept, r_squared))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::InternedTokens;
+
+    fn heaps(content: &str) -> HeapsFit {
+        let tokens = InternedTokens::new(content);
+        HeapsFit::from_ids(&tokens.ids, tokens.counts.len())
+    }
+
+    #[test]
+    fn test_linear_fit() {
+        let (slope, intercept, r_squared) =
+            linear_fit(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)]).unwrap();
+
+        assert_eq!((slope, intercept, r_squared), (2.0, 1.0, 1.0));
+        assert_eq!(linear_fit(&[(1.0, 1.0)]), None);
+        assert_eq!(linear_fit(&[(1.0, 1.0), (1.0, 2.0)]), None);
+    }
+
+    #[test]
+    fn test_zipf_on_ideal_distribution() {
+        let words = ["alpha", "beta", "gamma", "delta", "epsilon", "zeta"];
+        let word_map: HashMap<String, u32> = words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| (word.to_string(), 720 / (index as u32 + 1)))
+            .collect();
+
+        let zipf = ZipfFit::new(&word_map);
+
+        assert!((zipf.exponent - 1.0).abs() < 1e-9);
+        assert!((zipf.constant - 720.0).abs() < 1e-6);
+        assert!((zipf.r_squared - 1.0).abs() < 1e-9);
+        assert_eq!(zipf.rank_frequency[0], Point { x: 1.0, y: 720.0 });
+        assert_eq!(zipf.rank_frequency.len(), 6);
+    }
+
+    #[test]
+    fn test_rank_frequency_is_log_spaced() {
+        let word_map: HashMap<String, u32> = (0..1000)
+            .map(|index| (format!("w{index}"), 1000 - index))
+            .collect();
+
+        let zipf = ZipfFit::new(&word_map);
+
+        assert!(zipf.rank_frequency.len() < 100);
+        assert!(zipf.rank_frequency.windows(2).all(|w| w[0].x < w[1].x));
+    }
+
+    #[test]
+    fn test_heaps_growth_curve() {
+        let heaps = heaps("a b a c b d a e");
+
+        let distinct: Vec<f64> = heaps.vocabulary_growth.iter().map(|p| p.y).collect();
+        assert_eq!(distinct, vec![1.0, 2.0, 2.0, 3.0, 3.0, 4.0, 4.0, 5.0]);
+        assert!(heaps.beta > 0.0 && heaps.beta < 1.0);
+        assert!(heaps.r_squared > 0.9);
+    }
+
+    #[test]
+    fn test_heaps_samples_long_texts() {
+        // 700 distinct words made of letters only, as tokens must be.
+        let text: Vec<String> = (0..10_000)
+            .map(|index| {
+                let word = index % 700;
+                [word / 676, word / 26 % 26, word % 26]
+                    .iter()
+                    .map(|&letter| (b'a' + letter as u8) as char)
+                    .collect()
+            })
+            .collect();
+        let heaps = heaps(&text.join(" "));
+
+        assert_eq!(heaps.vocabulary_growth.len(), GROWTH_SAMPLES);
+        assert_eq!(
+            heaps.vocabulary_growth.last(),
+            Some(&Point {
+                x: 10_000.0,
+                y: 700.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(ZipfFit::new(&HashMap::new()), ZipfFit::default());
+        assert_eq!(heaps(""), HeapsFit::default());
+    }
+}
@@ -0,0 +1,187 @@
+/// Cuts the Project Gutenberg header and license footer off a plain-text
+/// ebook, returning the body between the "*** START OF ..." and
+/// "*** END OF ..." marker lines. Texts without the markers come back
+/// unchanged, apart from surrounding whitespace.
+pub fn strip_boilerplate(content: &str) -> &str {
+    let mut start = 0;
+    let mut end = content.len();
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        match marker(line) {
+            Some(upper) if upper.contains("START OF") && start == 0 => start = offset + line.len(),
+            Some(upper) if upper.contains("END OF") => {
+                end = offset;
+                break;
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+
+    content[start..end].trim()
+}
+
+/// `line` in upper case if it is a marker line: "*** START OF ...",
+/// "*** END OF ..." or the older "End of the Project Gutenberg EBook".
+fn marker(line: &str) -> Option<String> {
+    let upper = line.to_uppercase();
+    let is_marker = upper.contains("PROJECT GUTENBERG")
+        && (upper.trim_start().starts_with("***") || upper.trim_start().starts_with("END OF"));
+    is_marker.then_some(upper)
+}
+
+/// [`strip_boilerplate`] for text that arrives in chunks, such as a
+/// streamed download: each chunk gives back the body text it completes.
+/// Lines before a start marker are held until one turns up, so a text
+/// without markers is only passed on once it has been read to the end.
+#[derive(Default)]
+pub struct BoilerplateStripper {
+    /// The current line, not yet ended by a newline.
+    line: Vec<u8>,
+    /// Lines read before any start marker.
+    header: String,
+    started: bool,
+    ended: bool,
+    /// Whether body text has been passed on, so leading whitespace is not.
+    emitted: bool,
+    /// Whitespace held back in case it turns out to end the body.
+    trailing: String,
+}
+
+impl BoilerplateStripper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes raw UTF-8; lines are decoded whole, so characters split across
+    /// chunks are kept. Invalid sequences become U+FFFD.
+    pub fn push_bytes(&mut self, chunk: &[u8]) -> String {
+        let mut body = String::new();
+        let mut rest = chunk;
+        while let Some(newline) = rest.iter().position(|&byte| byte == b'\n') {
+            self.line.extend_from_slice(&rest[..=newline]);
+            rest = &rest[newline + 1..];
+            let line = std::mem::take(&mut self.line);
+            self.push_line(&String::from_utf8_lossy(&line), &mut body);
+        }
+        self.line.extend_from_slice(rest);
+        body
+    }
+
+    /// The body text still held back once the whole text has been pushed.
+    pub fn finish(mut self) -> String {
+        let mut body = String::new();
+        let line = std::mem::take(&mut self.line);
+        if !line.is_empty() {
+            self.push_line(&String::from_utf8_lossy(&line), &mut body);
+        }
+        if !self.ended {
+            let header = std::mem::take(&mut self.header);
+            self.emit(&header, &mut body);
+        }
+        body
+    }
+
+    fn push_line(&mut self, line: &str, body: &mut String) {
+        if self.ended {
+            return;
+        }
+        match marker(line) {
+            Some(upper) if upper.contains("START OF") && !self.started => {
+                self.started = true;
+                self.header.clear();
+                return;
+            }
+            Some(upper) if upper.contains("END OF") => {
+                self.ended = true;
+                // Without a start marker, the body is everything before.
+                let header = std::mem::take(&mut self.header);
+                self.emit(&header, body);
+                return;
+            }
+            _ => {}
+        }
+        if self.started {
+            self.emit(line, body);
+        } else {
+            self.header.push_str(line);
+        }
+    }
+
+    /// Passes `text` on as body, trimming whitespace off both ends of the
+    /// body as a whole.
+    fn emit(&mut self, text: &str, body: &mut String) {
+        let text = if self.emitted {
+            text
+        } else {
+            text.trim_start()
+        };
+        let content = text.trim_end();
+        if content.is_empty() {
+            self.trailing.push_str(text);
+            return;
+        }
+        body.push_str(&self.trailing);
+        body.push_str(content);
+        self.trailing = text[content.len()..].to_string();
+        self.emitted = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_boilerplate() {
+        let content = "The Project Gutenberg eBook of Emma\r\n\r\n\
+                       *** START OF THE PROJECT GUTENBERG EBOOK EMMA ***\r\n\r\n\
+                       EMMA\r\n\r\nChapter I\r\n\r\nEmma Woodhouse, handsome...\r\n\r\n\
+                       *** END OF THE PROJECT GUTENBERG EBOOK EMMA ***\r\n\
+                       Updated editions will replace the previous one.";
+
+        assert_eq!(
+            strip_boilerplate(content),
+            "EMMA\r\n\r\nChapter I\r\n\r\nEmma Woodhouse, handsome..."
+        );
+    }
+
+    #[test]
+    fn test_older_footer_marker() {
+        let content = "Header\n*** START OF THIS PROJECT GUTENBERG EBOOK X ***\nBody text.\n\
+                       End of the Project Gutenberg EBook of X\n*** END OF THIS PROJECT GUTENBERG EBOOK X ***\n";
+
+        assert_eq!(strip_boilerplate(content), "Body text.");
+    }
+
+    #[test]
+    fn test_without_markers() {
+        assert_eq!(strip_boilerplate("  Just a text.\n"), "Just a text.");
+    }
+
+    #[test]
+    fn test_stripper_matches_strip_boilerplate() {
+        let texts = [
+            "Header\r\n*** START OF THE PROJECT GUTENBERG EBOOK X ***\r\n\r\n  Body, \u{e9}t\u{e9}.\r\n\r\n\
+             More body.\r\n\r\n*** END OF THE PROJECT GUTENBERG EBOOK X ***\r\nLicense.",
+            "Header\n*** START OF THIS PROJECT GUTENBERG EBOOK X ***\nBody text.\n\
+             End of the Project Gutenberg EBook of X\n*** END OF THIS PROJECT GUTENBERG EBOOK X ***\n",
+            "  Just a text.\n\nIn two paragraphs. \n\n",
+            "Body before the footer.\n*** END OF THE PROJECT GUTENBERG EBOOK X ***\nLicense.",
+            "",
+        ];
+
+        for text in texts {
+            for size in 1..=text.len().max(1) {
+                let mut stripper = BoilerplateStripper::new();
+                let mut body = String::new();
+                for chunk in text.as_bytes().chunks(size) {
+                    body.push_str(&stripper.push_bytes(chunk));
+                }
+                body.push_str(&stripper.finish());
+                assert_eq!(body, strip_boilerplate(text), "chunks of {size}");
+            }
+        }
+    }
+}
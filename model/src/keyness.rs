@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stop_words::is_stopword;
+
+/// How distinctive words are ranked against the reference corpus.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum KeynessMeasure {
+    #[default]
+    TfIdf,
+    LogLikelihood,
+}
+
+/// Word statistics of the reference corpus a book is compared against. The
+/// book itself should not be part of it.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct CorpusFrequencies {
+    pub document_count: u32,
+    pub total_tokens: u64,
+    /// Number of documents each word occurs in.
+    pub document_frequencies: HashMap<String, u32>,
+    /// Occurrences of each word over all documents.
+    pub word_counts: HashMap<String, u64>,
+}
+
+#[cfg(test)]
+impl CorpusFrequencies {
+    /// Statistics of a corpus made of `books`, for tests.
+    pub(crate) fn from_books(books: &[HashMap<String, u32>]) -> Self {
+        let mut corpus = Self {
+            document_count: books.len() as u32,
+            ..Default::default()
+        };
+        for book in books {
+            for (word, &count) in book {
+                *corpus.document_frequencies.entry(word.clone()).or_insert(0) += 1;
+                *corpus.word_counts.entry(word.clone()).or_insert(0) += count as u64;
+                corpus.total_tokens += count as u64;
+            }
+        }
+        corpus
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct DistinctiveWord {
+    pub word: String,
+    pub count: u32,
+    pub document_frequency: u32,
+    pub tf_idf: f64,
+    /// Signed G²: negative when the word is rarer in the book than in the
+    /// corpus.
+    pub log_likelihood: f64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct DistinctiveWords {
+    pub measure: KeynessMeasure,
+    pub document_count: u32,
+    pub words: Vec<DistinctiveWord>,
+}
+
+impl DistinctiveWords {
+    /// Scores every non-stopword of a book's `word_map` against `corpus` and
+    /// keeps the `limit` highest-ranked ones. Only words the book uses more
+    /// often than the corpus are returned.
+    pub fn new(
+        word_map: &HashMap<String, u32>,
+        corpus: &CorpusFrequencies,
+        measure: KeynessMeasure,
+        limit: usize,
+    ) -> Self {
+        let book_tokens: u64 = word_map.values().map(|&count| count as u64).sum();
+        let documents = corpus.document_count as f64 + 1.0;
+
+        let mut words: Vec<DistinctiveWord> = word_map
+            .iter()
+            .filter(|(word, _)| !is_stopword(word))
+            .map(|(word, &count)| {
+                let document_frequency =
+                    corpus.document_frequencies.get(word).copied().unwrap_or(0);
+                let corpus_count = corpus.word_counts.get(word).copied().unwrap_or(0);
+
+                // The book counts as one more document containing the word.
+                let idf = (documents / (document_frequency as f64 + 1.0)).ln();
+                let tf = count as f64 / book_tokens as f64;
+
+                DistinctiveWord {
+                    word: word.clone(),
+                    count,
+                    document_frequency,
+                    tf_idf: tf * idf,
+                    log_likelihood: log_likelihood(
+                        count as u64,
+                        book_tokens,
+                        corpus_count,
+                        corpus.total_tokens,
+                    ),
+                }
+            })
+            .filter(|word| word.log_likelihood > 0.0)
+            .collect();
+
+        let score = |word: &DistinctiveWord| match measure {
+            KeynessMeasure::TfIdf => word.tf_idf,
+            KeynessMeasure::LogLikelihood => word.log_likelihood,
+        };
+        words.sort_by(|a, b| {
+            score(b)
+                .total_cmp(&score(a))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        words.truncate(limit);
+
+        Self {
+            measure,
+            document_count: corpus.document_count,
+            words,
+        }
+    }
+}
+
+/// Rayson and Garside's keyness: Dunning's G² for a word seen `count` times
+/// in `tokens` tokens against `reference_count` times in `reference_tokens`,
+/// signed by the direction of the difference.
+fn log_likelihood(count: u64, tokens: u64, reference_count: u64, reference_tokens: u64) -> f64 {
+    let (a, b) = (count as f64, reference_count as f64);
+    let (c, d) = (tokens as f64, reference_tokens as f64);
+    if c == 0.0 {
+        return 0.0;
+    }
+
+    let expected_book = c * (a + b) / (c + d);
+    let expected_reference = d * (a + b) / (c + d);
+    let mut g2 = 0.0;
+    if a > 0.0 {
+        g2 += a * (a / expected_book).ln();
+    }
+    if b > 0.0 {
+        g2 += b * (b / expected_reference).ln();
+    }
+    g2 *= 2.0;
+
+    if d > 0.0 && a / c < b / d {
+        -g2
+    } else {
+        g2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word_map(words: &[(&str, u32)]) -> HashMap<String, u32> {
+        words
+            .iter()
+            .map(|&(word, count)| (word.to_string(), count))
+            .collect()
+    }
+
+    /// Nine books of 1000 tokens: "sea" in all of them, "love" in five and
+    /// "whale" once.
+    fn corpus() -> CorpusFrequencies {
+        let mut books = vec![word_map(&[
+            ("sea", 10),
+            ("whale", 1),
+            ("love", 40),
+            ("the", 949),
+        ])];
+        books.extend((0..4).map(|_| word_map(&[("sea", 10), ("love", 40), ("the", 950)])));
+        books.extend((0..4).map(|_| word_map(&[("sea", 10), ("the", 990)])));
+        CorpusFrequencies::from_books(&books)
+    }
+
+    #[test]
+    fn test_tf_idf_ranks_rare_words_first() {
+        let book = word_map(&[("sea", 40), ("whale", 20), ("the", 500), ("love", 1)]);
+
+        let distinctive = DistinctiveWords::new(&book, &corpus(), KeynessMeasure::TfIdf, 10);
+
+        let words: Vec<&str> = distinctive.words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(words, vec!["whale", "sea"]);
+        assert!((distinctive.words[0].tf_idf - 20.0 / 561.0 * 5f64.ln()).abs() < 1e-12);
+        // Every document contains "sea", so its idf is zero.
+        assert_eq!(distinctive.words[1].tf_idf, 0.0);
+    }
+
+    #[test]
+    fn test_log_likelihood_keyness() {
+        assert!(log_likelihood(10, 1000, 100, 10000).abs() < 1e-9);
+        assert!(log_likelihood(50, 1000, 100, 10000) > 0.0);
+        assert!(log_likelihood(1, 1000, 100, 10000) < 0.0);
+        assert_eq!(log_likelihood(5, 100, 0, 0), 0.0);
+
+        let book = word_map(&[("sea", 40), ("whale", 20), ("love", 1)]);
+        let distinctive = DistinctiveWords::new(&book, &corpus(), KeynessMeasure::LogLikelihood, 1);
+
+        // Unlike TF-IDF, keyness rewards a common word used far more often.
+        assert_eq!(distinctive.words.len(), 1);
+        assert_eq!(distinctive.words[0].word, "sea");
+        assert_eq!(distinctive.document_count, 9);
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::book::tokenize;
+use crate::chapters::split_chapters;
+use crate::stop_words::is_stopword;
+use crate::utils::pagerank;
+
+/// Longer runs of content words are rarely real phrases.
+const MAX_PHRASE_WORDS: usize = 4;
+/// A phrase used only once says little about a whole book or chapter.
+const MIN_PHRASE_COUNT: u32 = 2;
+
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyphraseMethod {
+    /// Rose et al.'s Rapid Automatic Keyword Extraction.
+    #[default]
+    Rake,
+    /// Mihalcea and Tarau's TextRank over word co-occurrences.
+    TextRank,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Keyphrase {
+    pub phrase: String,
+    pub count: u32,
+    pub score: f64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Keyphrases {
+    pub method: KeyphraseMethod,
+    pub phrases: Vec<Keyphrase>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct ChapterKeyphrases {
+    pub index: usize,
+    pub title: String,
+    pub phrases: Vec<Keyphrase>,
+}
+
+impl Keyphrases {
+    /// The `limit` best multi-word phrases of `content` that occur at least
+    /// twice. Phrases are runs of content words between stopwords and
+    /// punctuation.
+    pub fn new(content: &str, method: KeyphraseMethod, limit: usize) -> Self {
+        let words = words(content);
+        let phrases = match method {
+            KeyphraseMethod::Rake => rake(&words),
+            KeyphraseMethod::TextRank => text_rank(&words),
+        };
+
+        Self {
+            method,
+            phrases: top(phrases, limit),
+        }
+    }
+
+    /// Keyphrases of every chapter of a book body.
+    pub fn by_chapter(
+        content: &str,
+        method: KeyphraseMethod,
+        limit: usize,
+    ) -> Vec<ChapterKeyphrases> {
+        split_chapters(content)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (title, text))| ChapterKeyphrases {
+                index,
+                title,
+                phrases: Self::new(text, method, limit).phrases,
+            })
+            .collect()
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Boundary {
+    None,
+    Clause,
+    Sentence,
+}
+
+/// A token and the punctuation that follows it.
+struct Word {
+    token: String,
+    stop: bool,
+    boundary: Boundary,
+}
+
+fn words(content: &str) -> Vec<Word> {
+    let mut words: Vec<Word> = Vec::new();
+    for raw in content.split_whitespace() {
+        // Opening quotes and brackets end whatever came before.
+        if raw.starts_with(['"', '“', '‘', '(', '[', '_']) {
+            if let Some(previous) = words.last_mut() {
+                if previous.boundary == Boundary::None {
+                    previous.boundary = Boundary::Clause;
+                }
+            }
+        }
+
+        let token = match tokenize(raw).next() {
+            Some(token) => token,
+            None => continue,
+        };
+        let trailing = &raw[raw.trim_end_matches(|ch: char| !ch.is_alphanumeric()).len()..];
+        let boundary = if trailing.contains(['.', '!', '?']) {
+            Boundary::Sentence
+        } else if !trailing.is_empty() {
+            Boundary::Clause
+        } else {
+            Boundary::None
+        };
+
+        words.push(Word {
+            stop: is_stopword(&token),
+            token,
+            boundary,
+        });
+    }
+    words
+}
+
+/// Runs of content words, each as the index range of its words.
+fn candidates(words: &[Word]) -> Vec<std::ops::Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (index, word) in words.iter().enumerate() {
+        if word.stop {
+            if let Some(begin) = start.take() {
+                runs.push(begin..index);
+            }
+            continue;
+        }
+        let begin = *start.get_or_insert(index);
+        if word.boundary != Boundary::None {
+            runs.push(begin..index + 1);
+            start = None;
+        }
+    }
+    if let Some(begin) = start {
+        runs.push(begin..words.len());
+    }
+    runs
+}
+
+/// Occurrences of each multi-word phrase among the candidate runs.
+fn phrase_counts(
+    words: &[Word],
+    runs: impl Iterator<Item = std::ops::Range<usize>>,
+) -> HashMap<Vec<&str>, u32> {
+    let mut counts = HashMap::new();
+    for run in runs {
+        if (2..=MAX_PHRASE_WORDS).contains(&run.len()) {
+            let phrase: Vec<&str> = words[run].iter().map(|w| w.token.as_str()).collect();
+            *counts.entry(phrase).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Scores each word by its degree over its frequency in the candidate runs,
+/// and each phrase by the sum of its words' scores.
+fn rake(words: &[Word]) -> Vec<Keyphrase> {
+    let runs = candidates(words);
+    let mut frequency: HashMap<&str, f64> = HashMap::new();
+    let mut degree: HashMap<&str, f64> = HashMap::new();
+    for run in &runs {
+        for word in &words[run.clone()] {
+            *frequency.entry(&word.token).or_insert(0.0) += 1.0;
+            *degree.entry(&word.token).or_insert(0.0) += run.len() as f64;
+        }
+    }
+
+    phrase_counts(words, runs.into_iter())
+        .into_iter()
+        .map(|(phrase, count)| Keyphrase {
+            score: phrase
+                .iter()
+                .map(|word| degree[word] / frequency[word])
+                .sum(),
+            phrase: phrase.join(" "),
+            count,
+        })
+        .collect()
+}
+
+/// Ranks content words by PageRank over a graph linking neighbouring
+/// content words of a sentence, keeps the top third as keywords and joins
+/// adjacent keywords into phrases scored by the sum of their words' ranks.
+fn text_rank(words: &[Word]) -> Vec<Keyphrase> {
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut edges: Vec<Vec<usize>> = Vec::new();
+    let mut previous: Option<usize> = None;
+    for word in words {
+        if !word.stop {
+            let next = ids.len();
+            let id = *ids.entry(&word.token).or_insert(next);
+            if id == edges.len() {
+                edges.push(Vec::new());
+            }
+            if let Some(other) = previous.filter(|&other| other != id) {
+                if !edges[id].contains(&other) {
+                    edges[id].push(other);
+                    edges[other].push(id);
+                }
+            }
+            previous = Some(id);
+        }
+        if word.boundary == Boundary::Sentence {
+            previous = None;
+        }
+    }
+
+    let n = edges.len();
+    let edges: Vec<Vec<(usize, f64)>> = edges
+        .into_iter()
+        .map(|node| node.into_iter().map(|j| (j, 1.0)).collect())
+        .collect();
+    let ranks = pagerank(&edges);
+
+    let mut ordered: Vec<f64> = ranks.clone();
+    ordered.sort_by(|a, b| b.total_cmp(a));
+    let threshold = ordered.get(n / 3).copied().unwrap_or(f64::INFINITY);
+    let rank = |word: &Word| ranks[ids[word.token.as_str()]];
+    let is_keyword = |word: &Word| !word.stop && rank(word) >= threshold;
+
+    // Maximal runs of keywords inside the candidate runs.
+    let mut keyword_runs = Vec::new();
+    for run in candidates(words) {
+        let mut start = None;
+        for index in run.clone() {
+            if is_keyword(&words[index]) {
+                start.get_or_insert(index);
+            } else if let Some(begin) = start.take() {
+                keyword_runs.push(begin..index);
+            }
+        }
+        if let Some(begin) = start {
+            keyword_runs.push(begin..run.end);
+        }
+    }
+
+    phrase_counts(words, keyword_runs.into_iter())
+        .into_iter()
+        .map(|(phrase, count)| Keyphrase {
+            score: phrase.iter().map(|word| ranks[ids[word]]).sum(),
+            phrase: phrase.join(" "),
+            count,
+        })
+        .collect()
+}
+
+fn top(phrases: Vec<Keyphrase>, limit: usize) -> Vec<Keyphrase> {
+    let mut phrases: Vec<Keyphrase> = phrases
+        .into_iter()
+        .filter(|phrase| phrase.count >= MIN_PHRASE_COUNT)
+        .collect();
+    phrases.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.count.cmp(&a.count))
+            .then_with(|| a.phrase.cmp(&b.phrase))
+    });
+    phrases.truncate(limit);
+    phrases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+The white whale swam past the old harbour. Ahab hunted the white whale
+for years. The old harbour was quiet; the white whale was not. Sailors in
+the old harbour told stories of the white whale and of lost ships.
+Lost ships, they said, filled the deep ocean floor.";
+
+    fn phrases(keyphrases: &Keyphrases) -> Vec<&str> {
+        keyphrases
+            .phrases
+            .iter()
+            .map(|phrase| phrase.phrase.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_candidates_split_at_stopwords_and_punctuation() {
+        let words = words("The white whale, old harbour. \"Lost ships\" and deep ocean");
+        let runs: Vec<Vec<&str>> = candidates(&words)
+            .into_iter()
+            .map(|run| words[run].iter().map(|w| w.token.as_str()).collect())
+            .collect();
+
+        assert_eq!(
+            runs,
+            vec![
+                vec!["white", "whale"],
+                vec!["old", "harbour"],
+                vec!["lost", "ships"],
+                vec!["deep", "ocean"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rake() {
+        let keyphrases = Keyphrases::new(TEXT, KeyphraseMethod::Rake, 10);
+
+        assert_eq!(
+            phrases(&keyphrases),
+            vec!["old harbour", "white whale", "lost ships"]
+        );
+        // "old harbour told stories" lifts the degree of both words to 8
+        // over 3 occurrences. The first "white whale" is part of a longer
+        // run and does not count as the phrase.
+        assert_eq!(keyphrases.phrases[0].score, 16.0 / 3.0);
+        assert_eq!(keyphrases.phrases[1].count, 3);
+    }
+
+    #[test]
+    fn test_text_rank() {
+        let keyphrases = Keyphrases::new(TEXT, KeyphraseMethod::TextRank, 10);
+
+        assert_eq!(keyphrases.method, KeyphraseMethod::TextRank);
+        assert_eq!(phrases(&keyphrases)[0], "white whale");
+        assert!(keyphrases.phrases.iter().all(|phrase| phrase.count >= 2));
+    }
+
+    #[test]
+    fn test_by_chapter() {
+        let book = format!("CHAPTER I\n\n{TEXT}\n\nCHAPTER II\n\n{TEXT}\n");
+
+        let chapters = Keyphrases::by_chapter(&book, KeyphraseMethod::Rake, 1);
+
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[1].title, "CHAPTER II");
+        assert_eq!(chapters[1].phrases[0].phrase, "old harbour");
+    }
+}
@@ -1,3 +1,29 @@
 pub mod book;
+pub mod builder;
+pub mod chapters;
+pub mod characters;
+pub mod clustering;
+pub mod comparison;
+pub mod concordance;
+pub mod corpus;
+pub mod dispersion;
+pub mod frequency_laws;
+pub mod gutenberg;
+pub mod keyness;
+pub mod keyphrases;
+pub mod ngrams;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod readability;
+pub mod reader;
+pub mod recommendations;
+pub mod richness;
+pub mod segmentation;
+pub mod sentiment;
+pub mod similarity;
 pub mod stop_words;
+pub mod stylometry;
+pub mod summary;
+pub mod topics;
 pub mod utils;
+pub mod vectors;
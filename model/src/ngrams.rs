@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::book::tokenize;
+use crate::stop_words::is_stopword;
+
+/// N-grams seen fewer times than this are too rare to score as collocations;
+/// PMI in particular wildly overrates one-off pairs.
+const MIN_COLLOCATION_COUNT: u32 = 3;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Ngram {
+    pub words: Vec<String>,
+    pub count: u32,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Collocation {
+    pub words: Vec<String>,
+    pub count: u32,
+    pub pmi: f64,
+    pub log_likelihood: f64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Ngrams {
+    pub n: usize,
+    pub top_ngrams: Vec<Ngram>,
+    pub collocations: Vec<Collocation>,
+}
+
+impl Ngrams {
+    /// Counts the n-grams of `content` and keeps the `limit` most frequent
+    /// ones, plus the `limit` strongest collocations by log-likelihood.
+    /// N-grams made up of stopwords only ("of the", "in the") are skipped.
+    pub fn new(content: &str, n: usize, limit: usize) -> Self {
+        let tokens: Vec<String> = tokenize(content).collect();
+
+        if n == 0 || tokens.len() < n {
+            return Self {
+                n,
+                top_ngrams: Vec::new(),
+                collocations: Vec::new(),
+            };
+        }
+
+        let mut unigram_counts: HashMap<&str, u32> = HashMap::new();
+        for token in &tokens {
+            *unigram_counts.entry(token.as_str()).or_insert(0) += 1;
+        }
+
+        let mut ngram_counts: HashMap<&[String], u32> = HashMap::new();
+        for window in tokens.windows(n) {
+            *ngram_counts.entry(window).or_insert(0) += 1;
+        }
+
+        let mut prefix_counts: HashMap<&[String], u32> = HashMap::new();
+        let mut suffix_counts: HashMap<&str, u32> = HashMap::new();
+        for (ngram, &count) in &ngram_counts {
+            *prefix_counts.entry(&ngram[..n - 1]).or_insert(0) += count;
+            *suffix_counts.entry(ngram[n - 1].as_str()).or_insert(0) += count;
+        }
+
+        let kept: Vec<(&[String], u32)> = ngram_counts
+            .iter()
+            .filter(|(ngram, _)| !ngram.iter().all(|word| is_stopword(word)))
+            .map(|(&ngram, &count)| (ngram, count))
+            .collect();
+
+        let mut top_ngrams: Vec<Ngram> = kept
+            .iter()
+            .map(|&(ngram, count)| Ngram {
+                words: ngram.to_vec(),
+                count,
+            })
+            .collect();
+        top_ngrams.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.words.cmp(&b.words)));
+        top_ngrams.truncate(limit);
+
+        let mut collocations: Vec<Collocation> = Vec::new();
+        if n >= 2 {
+            let total_tokens = tokens.len() as f64;
+            let total_ngrams = (tokens.len() - n + 1) as f64;
+
+            for &(ngram, count) in kept.iter().filter(|(_, c)| *c >= MIN_COLLOCATION_COUNT) {
+                let independent = ngram.iter().fold(1.0, |acc, word| {
+                    acc * unigram_counts[word.as_str()] as f64 / total_tokens
+                });
+                let pmi = (count as f64 / total_ngrams / independent).log2();
+
+                let log_likelihood = log_likelihood(
+                    count,
+                    prefix_counts[&ngram[..n - 1]],
+                    suffix_counts[ngram[n - 1].as_str()],
+                    total_ngrams as u32,
+                );
+
+                collocations.push(Collocation {
+                    words: ngram.to_vec(),
+                    count,
+                    pmi,
+                    log_likelihood,
+                });
+            }
+        }
+        collocations.sort_by(|a, b| {
+            b.log_likelihood
+                .total_cmp(&a.log_likelihood)
+                .then_with(|| a.words.cmp(&b.words))
+        });
+        collocations.truncate(limit);
+
+        Self {
+            n,
+            top_ngrams,
+            collocations,
+        }
+    }
+}
+
+/// Dunning's G² for the 2x2 contingency table of "first n-1 words" against
+/// "last word". For bigrams this is the textbook collocation score.
+fn log_likelihood(joint: u32, prefix: u32, suffix: u32, total: u32) -> f64 {
+    let observed = [
+        joint as f64,
+        (prefix - joint) as f64,
+        (suffix - joint) as f64,
+        (total + joint - prefix - suffix) as f64,
+    ];
+    let rows = [prefix as f64, (total - prefix) as f64];
+    let cols = [suffix as f64, (total - suffix) as f64];
+    let total = total as f64;
+
+    let mut g2 = 0.0;
+    for (index, &k) in observed.iter().enumerate() {
+        if k > 0.0 {
+            let expected = rows[index / 2] * cols[index % 2] / total;
+            g2 += k * (k / expected).ln();
+        }
+    }
+
+    2.0 * g2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(ngram: &[&str]) -> Vec<String> {
+        ngram.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_bigram_counts() {
+        let ngrams = Ngrams::new("Red fox. Red fox. Red fox. Blue hen.", 2, 10);
+
+        assert_eq!(ngrams.n, 2);
+        assert_eq!(
+            ngrams.top_ngrams[0],
+            Ngram {
+                words: words(&["red", "fox"]),
+                count: 3
+            }
+        );
+        assert_eq!(ngrams.top_ngrams.len(), 4);
+    }
+
+    #[test]
+    fn test_stopword_only_ngrams_are_skipped() {
+        let ngrams = Ngrams::new("out of the house and out of the garden", 2, 10);
+
+        assert!(!ngrams
+            .top_ngrams
+            .iter()
+            .any(|ngram| ngram.words == words(&["of", "the"])));
+        assert!(ngrams
+            .top_ngrams
+            .iter()
+            .any(|ngram| ngram.words == words(&["the", "house"])));
+    }
+
+    #[test]
+    fn test_trigrams_keep_inner_stopwords() {
+        let content = "said the king. said the king. said the king.";
+        let ngrams = Ngrams::new(content, 3, 1);
+
+        assert_eq!(ngrams.top_ngrams[0].words, words(&["said", "the", "king"]));
+        assert_eq!(ngrams.top_ngrams[0].count, 3);
+    }
+
+    #[test]
+    fn test_collocation_scores() {
+        let ngrams = Ngrams::new("x y x y x y", 2, 10);
+
+        assert_eq!(ngrams.collocations.len(), 1);
+        let collocation = &ngrams.collocations[0];
+        assert_eq!(collocation.words, words(&["x", "y"]));
+        assert!((collocation.pmi - 2.4f64.log2()).abs() < 1e-9);
+        assert!((collocation.log_likelihood - 6.7302).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_collocations_rank_fixed_phrases_first() {
+        let content = "Mr. Darcy smiled. The house was warm. Mr. Darcy left. \
+                       A cold night fell. Mr. Darcy returned. The house was warm.";
+        let ngrams = Ngrams::new(content, 2, 3);
+
+        assert_eq!(ngrams.collocations[0].words, words(&["mr", "darcy"]));
+    }
+
+    #[test]
+    fn test_limit_and_short_input() {
+        let ngrams = Ngrams::new("one two three four five six", 2, 2);
+        assert_eq!(ngrams.top_ngrams.len(), 2);
+
+        let ngrams = Ngrams::new("alone", 2, 10);
+        assert!(ngrams.top_ngrams.is_empty());
+        assert!(ngrams.collocations.is_empty());
+    }
+}
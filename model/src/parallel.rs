@@ -0,0 +1,48 @@
+use rayon::prelude::*;
+
+use crate::book::Analytics;
+use crate::builder::{batches, merge, BATCH_SIZE};
+
+/// Same as [`crate::builder::analyze`], but analyzes the batches of a long
+/// text on all cores before merging them in order.
+pub fn analyze(content: &str) -> Analytics {
+    let batches: Vec<Analytics> = batches(content, BATCH_SIZE)
+        .into_par_iter()
+        .map(Analytics::new)
+        .collect();
+    merge(batches.into_iter())
+}
+
+/// Analyzes many books concurrently, returning their analytics in order.
+pub fn analyze_books<T: AsRef<str> + Sync>(contents: &[T]) -> Vec<Analytics> {
+    contents
+        .par_iter()
+        .map(|content| analyze(content.as_ref()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let content: String = (0..30_000)
+            .map(|index| format!("Paragraph {index} of the whale.\nIt swam on.\n\n"))
+            .collect();
+        assert!(content.len() > 2 * BATCH_SIZE);
+
+        assert_eq!(analyze(&content), crate::builder::analyze(&content));
+    }
+
+    #[test]
+    fn test_analyze_books_keeps_order() {
+        let books = ["The whale.", "The sea and the ship.", ""];
+
+        let analytics = analyze_books(&books);
+
+        assert_eq!(analytics.len(), 3);
+        assert_eq!(analytics[1], Analytics::new(books[1]));
+        assert_eq!(analytics[2].get_total_word_count(), 0);
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::utils::mean;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct Readability {
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid_grade: f64,
+    pub gunning_fog: f64,
+    pub smog: f64,
+    pub coleman_liau: f64,
+    pub automated_readability_index: f64,
+}
+
+/// Estimates the number of syllables in an English word by counting vowel
+/// groups and discounting the usual silent endings ("make", "jumped",
+/// "takes"). Good enough for readability formulas, which were calibrated on
+/// hand counts that disagree with each other anyway.
+pub fn count_syllables(word: &str) -> u32 {
+    let chars: Vec<char> = word
+        .chars()
+        .filter(|ch| ch.is_alphabetic())
+        .flat_map(|ch| ch.to_lowercase())
+        .collect();
+    let len = chars.len();
+    if len == 0 {
+        return 0;
+    }
+    if len <= 3 {
+        return 1;
+    }
+
+    let is_vowel = |ch: char| matches!(ch, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for &ch in &chars {
+        let vowel = is_vowel(ch);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    let (last, second_last, third_last) = (chars[len - 1], chars[len - 2], chars[len - 3]);
+    let silent_ending = match (third_last, second_last, last) {
+        (c, 'l', 'e') => is_vowel(c),
+        (_, _, 'e') => !is_vowel(second_last),
+        (c, 'e', 'd') => !matches!(c, 't' | 'd') && !is_vowel(c),
+        (c, 'e', 's') => !matches!(c, 's' | 'x' | 'z' | 'c' | 'g' | 'h') && !is_vowel(c),
+        _ => false,
+    };
+    if silent_ending && count > 1 {
+        count -= 1;
+    }
+
+    count.max(1)
+}
+
+impl Readability {
+    /// Computes the classic readability formulas from word frequencies and
+    /// the sentence count. Words of three or more syllables count as
+    /// "complex" for Gunning Fog and as polysyllables for SMOG.
+    pub fn new(word_map: &HashMap<String, u32>, sentence_count: u32) -> Self {
+        let mut words = 0.0;
+        let mut letters = 0.0;
+        let mut syllables = 0.0;
+        let mut complex_words = 0.0;
+
+        for (word, &count) in word_map {
+            let count = count as f64;
+            let word_syllables = count_syllables(word);
+
+            words += count;
+            letters += word.graphemes(true).count() as f64 * count;
+            syllables += word_syllables as f64 * count;
+            if word_syllables >= 3 {
+                complex_words += count;
+            }
+        }
+
+        if words == 0.0 || sentence_count == 0 {
+            return Self::default();
+        }
+
+        let sentences = sentence_count as f64;
+        let words_per_sentence = words / sentences;
+        let syllables_per_word = syllables / words;
+        let letters_per_word = letters / words;
+
+        Self {
+            flesch_reading_ease: 206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+            flesch_kincaid_grade: 0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+            gunning_fog: 0.4 * (words_per_sentence + 100.0 * complex_words / words),
+            smog: 1.043 * (complex_words * 30.0 / sentences).sqrt() + 3.1291,
+            coleman_liau: 0.0588 * (100.0 * letters_per_word)
+                - 0.296 * (100.0 * sentences / words)
+                - 15.8,
+            automated_readability_index: 4.71 * letters_per_word + 0.5 * words_per_sentence - 21.43,
+        }
+    }
+
+    /// Mean of each score over several texts.
+    pub fn average(all: &[Self]) -> Self {
+        Self {
+            flesch_reading_ease: mean(all.iter().map(|r| r.flesch_reading_ease)),
+            flesch_kincaid_grade: mean(all.iter().map(|r| r.flesch_kincaid_grade)),
+            gunning_fog: mean(all.iter().map(|r| r.gunning_fog)),
+            smog: mean(all.iter().map(|r| r.smog)),
+            coleman_liau: mean(all.iter().map(|r| r.coleman_liau)),
+            automated_readability_index: mean(all.iter().map(|r| r.automated_readability_index)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::count_words;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_count_syllables() {
+        let cases = [
+            ("cat", 1),
+            ("the", 1),
+            ("hello", 2),
+            ("make", 1),
+            ("table", 2),
+            ("simple", 2),
+            ("jumped", 1),
+            ("wanted", 2),
+            ("takes", 1),
+            ("boxes", 2),
+            ("beautiful", 3),
+            ("readability", 5),
+            ("", 0),
+        ];
+
+        for (word, expected) in cases {
+            assert_eq!(count_syllables(word), expected, "{word}");
+        }
+    }
+
+    #[test]
+    fn test_monosyllabic_sentence() {
+        let readability = Readability::new(&count_words("The cat sat on the mat."), 1);
+
+        assert_close(readability.flesch_reading_ease, 116.145);
+        assert_close(readability.flesch_kincaid_grade, -1.45);
+        assert_close(readability.gunning_fog, 2.4);
+        assert_close(readability.smog, 3.1291);
+        assert_close(readability.coleman_liau, -4.0733);
+        assert_close(readability.automated_readability_index, -5.085);
+    }
+
+    #[test]
+    fn test_complex_words_raise_grade_levels() {
+        let simple = Readability::new(&count_words("The dog ran to the park and sat down."), 1);
+        let hard = Readability::new(
+            &count_words("Institutional considerations necessitate comprehensive deliberation."),
+            1,
+        );
+
+        assert!(hard.flesch_reading_ease < simple.flesch_reading_ease);
+        assert!(hard.flesch_kincaid_grade > simple.flesch_kincaid_grade);
+        assert!(hard.gunning_fog > simple.gunning_fog);
+        assert!(hard.smog > simple.smog);
+        assert!(hard.coleman_liau > simple.coleman_liau);
+        assert!(hard.automated_readability_index > simple.automated_readability_index);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(Readability::new(&HashMap::new(), 0), Readability::default());
+    }
+}
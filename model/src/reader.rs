@@ -0,0 +1,87 @@
+use std::ops::Range;
+
+use crate::segmentation::paragraphs;
+
+/// Splits `content` into pages of at least `page_size` characters, each
+/// ending at a paragraph break so no paragraph is cut in half. Returns the
+/// character range of every page; the last one may be shorter.
+pub fn paginate(content: &str, page_size: usize) -> Vec<Range<usize>> {
+    let mut pages = Vec::new();
+    let mut page_start = 0;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        offset += line.chars().count();
+        if line.trim().is_empty() && offset - page_start >= page_size {
+            pages.push(page_start..offset);
+            page_start = offset;
+        }
+    }
+    if offset > page_start {
+        pages.push(page_start..offset);
+    }
+
+    pages
+}
+
+/// The part of `content` between two character offsets, clamped to its
+/// length.
+pub fn char_slice(content: &str, start: usize, end: usize) -> &str {
+    let byte_offset = |chars: usize| {
+        content
+            .char_indices()
+            .nth(chars)
+            .map_or(content.len(), |(index, _)| index)
+    };
+
+    let start = byte_offset(start);
+    let end = byte_offset(end).max(start);
+    &content[start..end]
+}
+
+/// Undoes Gutenberg's hard wrapping: every paragraph becomes one line and
+/// paragraphs are separated by a blank line.
+pub fn reflow(text: &str) -> String {
+    paragraphs(text).join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "First paragraph,\nwrapped here.\n\nSecond one.\n\nThird\nparagraph.\n";
+
+    #[test]
+    fn test_paginate_on_paragraph_breaks() {
+        let pages = paginate(TEXT, 20);
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(
+            &TEXT[pages[0].clone()],
+            "First paragraph,\nwrapped here.\n\n"
+        );
+        assert_eq!(
+            &TEXT[pages[1].clone()],
+            "Second one.\n\nThird\nparagraph.\n"
+        );
+        assert_eq!(paginate(TEXT, 1).len(), 3);
+        assert!(paginate("", 100).is_empty());
+    }
+
+    #[test]
+    fn test_char_slice() {
+        let text = "Señor Don Quixote";
+
+        assert_eq!(char_slice(text, 0, 5), "Señor");
+        assert_eq!(char_slice(text, 6, 100), "Don Quixote");
+        assert_eq!(char_slice(text, 10, 3), "");
+    }
+
+    #[test]
+    fn test_reflow() {
+        assert_eq!(
+            reflow(TEXT),
+            "First paragraph, wrapped here.\n\nSecond one.\n\nThird paragraph."
+        );
+    }
+}
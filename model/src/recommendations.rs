@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+
+use crate::book::Book;
+
+// What each shared author, bookshelf and subject, and a shared language,
+// adds to the score of a similar book.
+pub const AUTHOR_WEIGHT: f64 = 3.0;
+pub const BOOKSHELF_WEIGHT: f64 = 2.0;
+pub const SUBJECT_WEIGHT: f64 = 1.0;
+pub const LANGUAGE_WEIGHT: f64 = 0.5;
+
+/// What similar books are found by.
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RecommendationMode {
+    /// Shared authors, bookshelves, subjects and language.
+    #[default]
+    Metadata,
+    /// Similar vocabulary, by content vectors.
+    Content,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Author,
+    Bookshelf,
+    Subject,
+    Language,
+    Vocabulary,
+}
+
+/// Something two books have in common and what it adds to the score.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct MatchReason {
+    pub kind: MatchKind,
+    pub name: String,
+    pub weight: f64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct SimilarBook {
+    pub book_id: i64,
+    pub title: String,
+    pub downloads: i32,
+    pub score: f64,
+    pub reasons: Vec<MatchReason>,
+}
+
+impl SimilarBook {
+    /// Scores each of `candidates` by the authors, bookshelves and subjects
+    /// it shares with `book`, plus a little for the same language, and
+    /// keeps the `limit` best. Equal scores go to the more downloaded book.
+    /// Sharing only the language does not make a book similar.
+    pub fn rank(book: &Book, candidates: &[Book], limit: usize) -> Vec<Self> {
+        let mut similar: Vec<Self> = candidates
+            .iter()
+            .filter(|candidate| candidate.book_id != book.book_id)
+            .filter_map(|candidate| {
+                let mut reasons = shared_reasons(book, candidate);
+                if reasons.is_empty() {
+                    return None;
+                }
+                if !book.language.is_empty() && book.language == candidate.language {
+                    reasons.push(MatchReason {
+                        kind: MatchKind::Language,
+                        name: book.language.clone(),
+                        weight: LANGUAGE_WEIGHT,
+                    });
+                }
+
+                Some(Self {
+                    book_id: candidate.book_id,
+                    title: candidate.title.clone(),
+                    downloads: candidate.downloads,
+                    score: reasons.iter().map(|reason| reason.weight).sum(),
+                    reasons,
+                })
+            })
+            .collect();
+
+        similar.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.downloads.cmp(&a.downloads))
+                .then_with(|| a.book_id.cmp(&b.book_id))
+        });
+        similar.truncate(limit);
+        similar
+    }
+}
+
+fn shared_reasons(book: &Book, candidate: &Book) -> Vec<MatchReason> {
+    let reason = |kind, name: &str, weight| MatchReason {
+        kind,
+        name: name.to_string(),
+        weight,
+    };
+    let mut reasons = Vec::new();
+
+    for author in &book.authors {
+        if candidate
+            .authors
+            .iter()
+            .any(|other| other.author_id == author.author_id)
+        {
+            reasons.push(reason(
+                MatchKind::Author,
+                &author.author_name,
+                AUTHOR_WEIGHT,
+            ));
+        }
+    }
+
+    let other_shelves = candidate.bookshelves.as_deref().unwrap_or_default();
+    for shelf in book.bookshelves.as_deref().unwrap_or_default() {
+        if other_shelves
+            .iter()
+            .any(|other| other.shelf_id == shelf.shelf_id)
+        {
+            reasons.push(reason(
+                MatchKind::Bookshelf,
+                &shelf.shelf_name,
+                BOOKSHELF_WEIGHT,
+            ));
+        }
+    }
+
+    let other_subjects = candidate.subjects.as_deref().unwrap_or_default();
+    for subject in book.subjects.as_deref().unwrap_or_default() {
+        if other_subjects
+            .iter()
+            .any(|other| other.subject_id == subject.subject_id)
+        {
+            reasons.push(reason(
+                MatchKind::Subject,
+                &subject.subject_name,
+                SUBJECT_WEIGHT,
+            ));
+        }
+    }
+
+    reasons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::{Author, Bookshelf, Subject};
+
+    fn book(book_id: i64, authors: &[i32], shelves: &[i32], subjects: &[i32]) -> Book {
+        Book {
+            book_id,
+            title: format!("Book {book_id}"),
+            language: "English".to_string(),
+            downloads: book_id as i32,
+            authors: authors
+                .iter()
+                .map(|&author_id| Author {
+                    author_id,
+                    author_name: format!("Author {author_id}"),
+                    year_of_birth: None,
+                    year_of_death: None,
+                })
+                .collect(),
+            bookshelves: Some(
+                shelves
+                    .iter()
+                    .map(|&shelf_id| Bookshelf {
+                        shelf_name: format!("Shelf {shelf_id}"),
+                        shelf_id,
+                    })
+                    .collect(),
+            ),
+            subjects: Some(
+                subjects
+                    .iter()
+                    .map(|&subject_id| Subject {
+                        subject_name: format!("Subject {subject_id}"),
+                        subject_id,
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rank_by_weighted_overlap() {
+        let target = book(1, &[1], &[1], &[1, 2]);
+        let candidates = [
+            target.clone(),
+            book(2, &[], &[], &[1, 2]),
+            book(3, &[1], &[], &[]),
+            book(4, &[], &[2], &[3]),
+            book(5, &[], &[1], &[]),
+            book(6, &[], &[], &[2]),
+        ];
+
+        let similar = SimilarBook::rank(&target, &candidates, 3);
+
+        let ids: Vec<i64> = similar.iter().map(|book| book.book_id).collect();
+        // Books 2 and 5 tie on score; the more downloaded one comes first.
+        assert_eq!(ids, vec![3, 5, 2]);
+        assert_eq!(similar[0].score, AUTHOR_WEIGHT + LANGUAGE_WEIGHT);
+        assert_eq!(
+            similar[0].reasons[0],
+            MatchReason {
+                kind: MatchKind::Author,
+                name: "Author 1".to_string(),
+                weight: AUTHOR_WEIGHT,
+            }
+        );
+    }
+
+    #[test]
+    fn test_language_alone_is_not_similar() {
+        let target = book(1, &[1], &[], &[]);
+        let mut other = book(2, &[1], &[], &[]);
+        other.language = "French".to_string();
+
+        let similar = SimilarBook::rank(&target, &[book(3, &[2], &[], &[]), other], 10);
+
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].book_id, 2);
+        assert_eq!(similar[0].score, AUTHOR_WEIGHT);
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils::mean;
+
+/// Window size for the moving-average type-token ratio, as recommended by
+/// Covington and McFall (2010) for texts of book length.
+pub const MATTR_WINDOW: usize = 500;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct LexicalRichness {
+    pub type_token_ratio: f64,
+    pub moving_average_ttr: f64,
+    pub hapax_legomena: u32,
+    pub dis_legomena: u32,
+    pub yules_k: f64,
+    pub simpsons_d: f64,
+    /// Undefined when every word occurs exactly once.
+    pub honores_r: Option<f64>,
+}
+
+impl LexicalRichness {
+    /// Computes the richness measures from the word frequencies. The
+    /// moving-average TTR needs the tokens in reading order, so it is
+    /// computed by the caller with [`interned_moving_average_ttr`].
+    pub fn from_word_map(word_map: &HashMap<String, u32>, moving_average_ttr: f64) -> Self {
+        let types = word_map.len() as f64;
+        let total: u64 = word_map.values().map(|&count| count as u64).sum();
+        if total == 0 {
+            return Self::default();
+        }
+        let tokens_f = total as f64;
+
+        let hapax_legomena = word_map.values().filter(|&&count| count == 1).count() as u32;
+        let dis_legomena = word_map.values().filter(|&&count| count == 2).count() as u32;
+
+        let sum_of_squares: f64 = word_map
+            .values()
+            .map(|&count| count as f64 * count as f64)
+            .sum();
+        let yules_k = 10_000.0 * (sum_of_squares - tokens_f) / (tokens_f * tokens_f);
+
+        let simpsons_d = if total > 1 {
+            (sum_of_squares - tokens_f) / (tokens_f * (tokens_f - 1.0))
+        } else {
+            0.0
+        };
+
+        let honores_r = if (hapax_legomena as f64) < types {
+            Some(100.0 * tokens_f.ln() / (1.0 - hapax_legomena as f64 / types))
+        } else {
+            None
+        };
+
+        Self {
+            type_token_ratio: types / tokens_f,
+            moving_average_ttr,
+            hapax_legomena,
+            dis_legomena,
+            yules_k,
+            simpsons_d,
+            honores_r,
+        }
+    }
+
+    /// Mean of each measure over several texts; Honoré's R over the texts
+    /// where it is defined.
+    pub fn average(all: &[Self]) -> Self {
+        let honores_r: Vec<f64> = all.iter().filter_map(|r| r.honores_r).collect();
+
+        Self {
+            type_token_ratio: mean(all.iter().map(|r| r.type_token_ratio)),
+            moving_average_ttr: mean(all.iter().map(|r| r.moving_average_ttr)),
+            hapax_legomena: mean(all.iter().map(|r| r.hapax_legomena as f64)).round() as u32,
+            dis_legomena: mean(all.iter().map(|r| r.dis_legomena as f64)).round() as u32,
+            yules_k: mean(all.iter().map(|r| r.yules_k)),
+            simpsons_d: mean(all.iter().map(|r| r.simpsons_d)),
+            honores_r: (!honores_r.is_empty()).then(|| mean(honores_r.into_iter())),
+        }
+    }
+}
+
+/// Mean type-token ratio over every window of `window` consecutive tokens,
+/// given as interned `ids` below `types`. Texts shorter than the window fall
+/// back to the plain type-token ratio.
+pub fn interned_moving_average_ttr(ids: &[u32], types: usize, window: usize) -> f64 {
+    if ids.is_empty() || window == 0 {
+        return 0.0;
+    }
+    let window = window.min(ids.len());
+
+    let mut counts = vec![0u32; types];
+    let mut distinct = 0;
+    for &id in &ids[..window] {
+        if counts[id as usize] == 0 {
+            distinct += 1;
+        }
+        counts[id as usize] += 1;
+    }
+    let mut ratio_sum = distinct as f64 / window as f64;
+
+    for (&outgoing, &incoming) in ids.iter().zip(&ids[window..]) {
+        if counts[incoming as usize] == 0 {
+            distinct += 1;
+        }
+        counts[incoming as usize] += 1;
+        counts[outgoing as usize] -= 1;
+        if counts[outgoing as usize] == 0 {
+            distinct -= 1;
+        }
+        ratio_sum += distinct as f64 / window as f64;
+    }
+
+    ratio_sum / (ids.len() - window + 1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::InternedTokens;
+
+    fn analyze(content: &str) -> LexicalRichness {
+        let tokens = InternedTokens::new(content);
+        LexicalRichness::from_word_map(
+            &tokens.word_map(),
+            moving_average_ttr(content, MATTR_WINDOW),
+        )
+    }
+
+    fn moving_average_ttr(content: &str, window: usize) -> f64 {
+        let tokens = InternedTokens::new(content);
+        interned_moving_average_ttr(&tokens.ids, tokens.counts.len(), window)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn test_reference_sentence() {
+        // the x4, cat x2, saw x2, dog x2, and x1, run x1: N = 12, V = 6
+        let richness = analyze("The cat saw the dog, and the dog saw the cat run.");
+
+        assert_close(richness.type_token_ratio, 0.5);
+        assert_close(richness.moving_average_ttr, 0.5);
+        assert_eq!(richness.hapax_legomena, 2);
+        assert_eq!(richness.dis_legomena, 3);
+        assert_close(richness.yules_k, 1250.0);
+        assert_close(richness.simpsons_d, 18.0 / 132.0);
+        assert_close(
+            richness.honores_r.unwrap(),
+            100.0 * 12f64.ln() / (1.0 - 2.0 / 6.0),
+        );
+    }
+
+    /// The Bliss copy of the Gettysburg Address, usually counted as 272
+    /// words; "battle-field" is a single token here.
+    const GETTYSBURG: &str = "\
+Four score and seven years ago our fathers brought forth on this continent,
+a new nation, conceived in Liberty, and dedicated to the proposition that
+all men are created equal.
+
+Now we are engaged in a great civil war, testing whether that nation, or any
+nation so conceived and so dedicated, can long endure. We are met on a great
+battle-field of that war. We have come to dedicate a portion of that field,
+as a final resting place for those who here gave their lives that that
+nation might live. It is altogether fitting and proper that we should do
+this.
+
+But, in a larger sense, we can not dedicate -- we can not consecrate -- we
+can not hallow -- this ground. The brave men, living and dead, who struggled
+here, have consecrated it, far above our poor power to add or detract. The
+world will little note, nor long remember what we say here, but it can never
+forget what they did here. It is for us the living, rather, to be dedicated
+here to the unfinished work which they who fought here have thus far so
+nobly advanced. It is rather for us to be here dedicated to the great task
+remaining before us -- that from these honored dead we take increased
+devotion to that cause for which they gave the last full measure of devotion
+-- that we here highly resolve that these dead shall not have died in vain
+-- that this nation, under God, shall have a new birth of freedom -- and
+that government of the people, by the people, for the people, shall not
+perish from the earth.";
+
+    #[test]
+    fn test_gettysburg_address() {
+        let richness = analyze(GETTYSBURG);
+
+        // Frequency spectrum V(m): how many words occur exactly m times.
+        let spectrum: [(f64, f64); 11] = [
+            (1.0, 91.0),
+            (2.0, 20.0),
+            (3.0, 10.0),
+            (4.0, 3.0),
+            (5.0, 7.0),
+            (6.0, 1.0),
+            (7.0, 1.0),
+            (8.0, 2.0),
+            (10.0, 1.0),
+            (11.0, 1.0),
+            (13.0, 1.0),
+        ];
+        let n: f64 = spectrum.iter().map(|(m, v)| m * v).sum();
+        let v: f64 = spectrum.iter().map(|(_, v)| v).sum();
+        assert_eq!((n, v), (271.0, 138.0));
+
+        // The spectrum forms given by Tweedie and Baayen (1998).
+        let squares: f64 = spectrum.iter().map(|(m, v)| m * m * v).sum();
+        let pairs: f64 = spectrum.iter().map(|(m, v)| m * (m - 1.0) * v).sum();
+        assert_close(richness.type_token_ratio, v / n);
+        assert_eq!(richness.hapax_legomena, 91);
+        assert_eq!(richness.dis_legomena, 20);
+        assert_close(richness.yules_k, 10_000.0 * (squares - n) / (n * n));
+        assert_close(richness.yules_k, 111.110);
+        assert_close(richness.simpsons_d, pairs / (n * (n - 1.0)));
+        assert_close(richness.honores_r.unwrap(), 1644.877);
+    }
+
+    #[test]
+    fn test_all_hapaxes() {
+        let richness = analyze("every word here is unique");
+
+        assert_close(richness.type_token_ratio, 1.0);
+        assert_eq!(richness.hapax_legomena, 5);
+        assert_close(richness.yules_k, 0.0);
+        assert_close(richness.simpsons_d, 0.0);
+        assert_eq!(richness.honores_r, None);
+    }
+
+    #[test]
+    fn test_moving_average_ttr() {
+        assert_close(moving_average_ttr("a b a b c", 2), 1.0);
+        assert_close(moving_average_ttr("a b a b c", 3), 7.0 / 9.0);
+        assert_close(moving_average_ttr("a b a b c", 10), 3.0 / 5.0);
+    }
+
+    #[test]
+    fn test_moving_average_ttr_is_length_robust() {
+        let verse = "the sea was calm and the night was dark ";
+        let short_ttr = analyze(&verse.repeat(60)).type_token_ratio;
+        let long_ttr = analyze(&verse.repeat(600)).type_token_ratio;
+        assert!(long_ttr < short_ttr / 5.0);
+
+        assert_close(
+            moving_average_ttr(&verse.repeat(60), MATTR_WINDOW),
+            moving_average_ttr(&verse.repeat(600), MATTR_WINDOW),
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(analyze(""), LexicalRichness::default());
+    }
+}
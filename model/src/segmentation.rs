@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, HashSet};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::book::count_tokens;
+
+lazy_static! {
+    /// Abbreviations whose trailing period UAX #29 mistakes for the end of a
+    /// sentence whenever a capitalised word follows ("Mr. Darcy").
+    static ref ABBREVIATIONS: HashSet<&'static str> = {
+        let abbreviations: [&str; 30] = [
+            "mr", "mrs", "ms", "messrs", "mme", "mlle", "dr", "st", "jr", "sr", "esq", "capt",
+            "col", "gen", "lieut", "lt", "sgt", "maj", "adm", "rev", "prof", "hon", "gov",
+            "mt", "ft", "vol", "viz", "cf", "e.g", "i.e",
+        ];
+        abbreviations.iter().cloned().collect()
+    };
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct SentenceLengthBin {
+    pub length: u32,
+    pub count: u32,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct TextStructure {
+    pub sentence_count: u32,
+    pub paragraph_count: u32,
+    pub mean_sentence_length: f64,
+    pub median_sentence_length: f64,
+    pub sentence_length_histogram: Vec<SentenceLengthBin>,
+}
+
+/// Splits hard-wrapped text into paragraphs on blank lines and joins the
+/// wrapped lines of each paragraph back together with single spaces.
+pub fn paragraphs(content: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join(" "));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join(" "));
+    }
+
+    paragraphs
+}
+
+/// Splits a single paragraph into sentences on UAX #29 sentence boundaries,
+/// without breaking after abbreviations such as "Mr." or initials like "J.".
+pub fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (index, piece) in text.split_sentence_bound_indices() {
+        let begin = *start.get_or_insert(index);
+        let end = index + piece.len();
+        if ends_with_abbreviation(&text[begin..end]) {
+            continue;
+        }
+
+        let sentence = text[begin..end].trim();
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+        start = None;
+    }
+    if let Some(begin) = start {
+        let sentence = text[begin..].trim();
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+    }
+
+    sentences
+}
+
+fn ends_with_abbreviation(sentence: &str) -> bool {
+    let last_word = match sentence.split_whitespace().last() {
+        Some(word) => word.trim_start_matches(|ch: char| !ch.is_alphabetic()),
+        None => return false,
+    };
+    let body = match last_word.strip_suffix('.') {
+        Some(body) if !body.is_empty() => body,
+        _ => return false,
+    };
+
+    let mut chars = body.chars();
+    let is_initial =
+        matches!((chars.next(), chars.next()), (Some(ch), None) if ch.is_uppercase() && ch != 'I');
+
+    is_initial || ABBREVIATIONS.contains(body.to_lowercase().as_str())
+}
+
+impl TextStructure {
+    pub fn new(content: &str) -> Self {
+        let mut lengths: BTreeMap<u32, u32> = BTreeMap::new();
+        let mut paragraph_count = 0;
+
+        for paragraph in paragraphs(content) {
+            let mut has_words = false;
+            for sentence in sentences(&paragraph) {
+                let length = count_tokens(sentence) as u32;
+                if length > 0 {
+                    *lengths.entry(length).or_insert(0) += 1;
+                    has_words = true;
+                }
+            }
+            if has_words {
+                paragraph_count += 1;
+            }
+        }
+
+        let histogram = lengths
+            .into_iter()
+            .map(|(length, count)| SentenceLengthBin { length, count })
+            .collect();
+
+        Self::from_histogram(paragraph_count, histogram)
+    }
+
+    /// Combines the counts of two texts, as if they were read one after the
+    /// other.
+    pub fn merge(&mut self, other: &Self) {
+        let mut lengths: BTreeMap<u32, u32> = BTreeMap::new();
+        for bin in self
+            .sentence_length_histogram
+            .iter()
+            .chain(&other.sentence_length_histogram)
+        {
+            *lengths.entry(bin.length).or_insert(0) += bin.count;
+        }
+
+        let histogram = lengths
+            .into_iter()
+            .map(|(length, count)| SentenceLengthBin { length, count })
+            .collect();
+
+        *self = Self::from_histogram(self.paragraph_count + other.paragraph_count, histogram);
+    }
+
+    /// Derives the summary statistics from a histogram sorted by length.
+    fn from_histogram(paragraph_count: u32, histogram: Vec<SentenceLengthBin>) -> Self {
+        let sentence_count: u32 = histogram.iter().map(|bin| bin.count).sum();
+        let total_words: u64 = histogram
+            .iter()
+            .map(|bin| bin.length as u64 * bin.count as u64)
+            .sum();
+
+        let mean_sentence_length = if sentence_count > 0 {
+            total_words as f64 / sentence_count as f64
+        } else {
+            0.0
+        };
+
+        let median_sentence_length = if sentence_count > 0 {
+            let lower = nth_length(&histogram, (sentence_count - 1) / 2);
+            let upper = nth_length(&histogram, sentence_count / 2);
+            (lower + upper) as f64 / 2.0
+        } else {
+            0.0
+        };
+
+        Self {
+            sentence_count,
+            paragraph_count,
+            mean_sentence_length,
+            median_sentence_length,
+            sentence_length_histogram: histogram,
+        }
+    }
+}
+
+/// The length of the `n`-th shortest sentence (zero-based).
+fn nth_length(histogram: &[SentenceLengthBin], n: u32) -> u32 {
+    let mut seen = 0;
+    for bin in histogram {
+        seen += bin.count;
+        if seen > n {
+            return bin.length;
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paragraphs_reflow_wrapped_lines() {
+        let content =
+            "It was the best of times,\r\nit was the worst of times.\r\n\r\n\r\nThe end.\n";
+        let paragraphs = paragraphs(content);
+
+        assert_eq!(
+            paragraphs,
+            vec![
+                "It was the best of times, it was the worst of times.".to_string(),
+                "The end.".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sentences() {
+        let sentences = sentences("Call me Ishmael. Some years ago, never mind how long! Why?");
+
+        assert_eq!(
+            sentences,
+            vec![
+                "Call me Ishmael.",
+                "Some years ago, never mind how long!",
+                "Why?"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sentences_keep_abbreviations() {
+        let sentences =
+            sentences("Mr. Darcy met Mrs. Bennet on St. James Street. J. Smith was there.");
+
+        assert_eq!(
+            sentences,
+            vec![
+                "Mr. Darcy met Mrs. Bennet on St. James Street.",
+                "J. Smith was there."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_structure() {
+        let content =
+            "One two three. One two three.\nOne.\n\nOne two three four five six.\n\n* * *\n";
+        let structure = TextStructure::new(content);
+
+        assert_eq!(structure.sentence_count, 4);
+        assert_eq!(structure.paragraph_count, 2);
+        assert_eq!(structure.mean_sentence_length, 13.0 / 4.0);
+        assert_eq!(structure.median_sentence_length, 3.0);
+        assert_eq!(
+            structure.sentence_length_histogram,
+            vec![
+                SentenceLengthBin {
+                    length: 1,
+                    count: 1
+                },
+                SentenceLengthBin {
+                    length: 3,
+                    count: 2
+                },
+                SentenceLengthBin {
+                    length: 6,
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_structure_empty() {
+        let structure = TextStructure::new("");
+
+        assert_eq!(structure.sentence_count, 0);
+        assert_eq!(structure.paragraph_count, 0);
+        assert_eq!(structure.mean_sentence_length, 0.0);
+        assert_eq!(structure.median_sentence_length, 0.0);
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::book::tokenize;
+use crate::concordance::stem;
+use crate::dispersion::segment_start;
+
+/// A negator flips the valence of a lexicon word up to this many tokens
+/// after it, as in "not at all happy".
+const NEGATION_SCOPE: usize = 3;
+const NEGATORS: [&str; 18] = [
+    "not", "no", "never", "nor", "neither", "nobody", "nothing", "nowhere", "without", "cannot",
+    "cant", "dont", "didnt", "doesnt", "isnt", "wasnt", "wont", "hardly",
+];
+
+lazy_static! {
+    static ref LEXICON: HashMap<&'static str, i8> = include_str!("../data/sentiment.tsv")
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (word, valence) = line.split_once('\t')?;
+            Some((word, valence.parse().ok()?))
+        })
+        .collect();
+    /// Valences by stem, for inflections the lexicon does not list. Stems
+    /// shared by words of different valence are left out.
+    static ref STEMS: HashMap<String, i8> = {
+        let mut stems: HashMap<String, Option<i8>> = HashMap::new();
+        for (word, &valence) in LEXICON.iter() {
+            stems
+                .entry(stem(word))
+                .and_modify(|known| {
+                    if *known != Some(valence) {
+                        *known = None;
+                    }
+                })
+                .or_insert(Some(valence));
+        }
+        stems
+            .into_iter()
+            .filter_map(|(stem, valence)| Some((stem, valence?)))
+            .collect()
+    };
+}
+
+/// Valence of `word` in the bundled lexicon, from -3 to 3. Words it does
+/// not list are looked up by their stem.
+pub fn valence(word: &str) -> Option<i8> {
+    LEXICON
+        .get(word)
+        .or_else(|| STEMS.get(&stem(word)))
+        .copied()
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct SentimentPoint {
+    pub segment: usize,
+    /// Token offset where the segment starts.
+    pub start_token: usize,
+    /// Summed valence of the segment's lexicon words per token.
+    pub score: f64,
+    /// Moving average of `score` over the surrounding segments.
+    pub smoothed: f64,
+}
+
+/// The emotional arc of a text: its sentiment from beginning to end, in
+/// equally sized segments.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct SentimentArc {
+    pub total_tokens: usize,
+    pub segments: usize,
+    pub window: usize,
+    pub points: Vec<SentimentPoint>,
+}
+
+impl SentimentArc {
+    /// Scores each of `segments` equal parts of `content` with the bundled
+    /// lexicon and smooths the scores with a centred moving average over
+    /// `window` segments.
+    pub fn new(content: &str, segments: usize, window: usize) -> Self {
+        let tokens: Vec<String> = tokenize(content).collect();
+        let total = tokens.len();
+        let segments = segments.clamp(1, total.max(1));
+        let window = window.clamp(1, segments);
+
+        let mut sums = vec![0.0; segments];
+        let mut negated_until = 0;
+        for (position, token) in tokens.iter().enumerate() {
+            if NEGATORS.contains(&token.as_str()) {
+                negated_until = position + NEGATION_SCOPE + 1;
+                continue;
+            }
+            if let Some(valence) = valence(token) {
+                let sign = if position < negated_until { -1.0 } else { 1.0 };
+                sums[position * segments / total] += sign * valence as f64;
+            }
+        }
+
+        let scores: Vec<f64> = sums
+            .iter()
+            .enumerate()
+            .map(|(segment, sum)| {
+                let size = segment_start(segment + 1, segments, total)
+                    - segment_start(segment, segments, total);
+                if size > 0 {
+                    sum / size as f64
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let smoothed = moving_average(&scores, window);
+
+        let points = scores
+            .into_iter()
+            .zip(smoothed)
+            .enumerate()
+            .map(|(segment, (score, smoothed))| SentimentPoint {
+                segment,
+                start_token: segment_start(segment, segments, total),
+                score,
+                smoothed,
+            })
+            .collect();
+
+        Self {
+            total_tokens: total,
+            segments,
+            window,
+            points,
+        }
+    }
+}
+
+/// Centred moving average, over fewer values near both ends.
+fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let before = (window - 1) / 2;
+    let after = window / 2;
+    (0..values.len())
+        .map(|i| {
+            let neighbours = &values[i.saturating_sub(before)..(i + after + 1).min(values.len())];
+            neighbours.iter().sum::<f64>() / neighbours.len() as f64
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lexicon_is_bundled() {
+        assert_eq!(valence("happy"), Some(3));
+        assert_eq!(valence("despair"), Some(-3));
+        assert_eq!(valence("whale"), None);
+        assert!(LEXICON.values().all(|valence| (-3..=3).contains(valence)));
+    }
+
+    #[test]
+    fn test_unlisted_inflections_match_by_stem() {
+        assert_eq!(LEXICON.get("abandons"), None);
+        assert_eq!(valence("abandons"), valence("abandon"));
+        assert_eq!(valence("abandoning"), Some(-2));
+    }
+
+    #[test]
+    fn test_arc_follows_the_story() {
+        let text = "They were happy and loved the lovely garden. \
+                    Then came the war, the terror and the death of friends. \
+                    At last peace returned and joy with it.";
+
+        let arc = SentimentArc::new(text, 3, 1);
+
+        assert_eq!(arc.points.len(), 3);
+        assert!(arc.points[0].score > 0.0);
+        assert!(arc.points[1].score < 0.0);
+        assert!(arc.points[2].score > 0.0);
+        assert_eq!(arc.points[0].smoothed, arc.points[0].score);
+        assert_eq!(arc.points[1].start_token, arc.total_tokens / 3);
+    }
+
+    #[test]
+    fn test_negation_flips_valence() {
+        let plain = SentimentArc::new("she was happy", 1, 1);
+        let negated = SentimentArc::new("she was not at all happy", 1, 1);
+
+        assert_eq!(plain.points[0].score, 1.0);
+        assert_eq!(negated.points[0].score, -0.5);
+        assert_eq!(SentimentArc::new("", 10, 5).points[0].score, 0.0);
+    }
+
+    #[test]
+    fn test_moving_average() {
+        assert_eq!(
+            moving_average(&[3.0, 0.0, 3.0, 6.0], 3),
+            vec![1.5, 2.0, 3.0, 4.5]
+        );
+        assert_eq!(moving_average(&[1.0, 2.0], 1), vec![1.0, 2.0]);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::comparison::{self, jaccard};
+use crate::stop_words::is_stopword;
+
+/// One step of agglomerative clustering. Books are clusters `0..n`, and the
+/// cluster formed by the `i`-th merge is numbered `n + i`, as in SciPy's
+/// linkage matrices.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Merge {
+    pub left: usize,
+    pub right: usize,
+    /// Average cosine distance between the books of the two clusters.
+    pub distance: f64,
+    /// Number of books in the merged cluster.
+    pub size: usize,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct SimilarityMatrix {
+    pub book_ids: Vec<i64>,
+    /// Cosine similarity of the books' TF-IDF vectors.
+    pub cosine: Vec<Vec<f64>>,
+    /// Jaccard overlap of the books' vocabularies.
+    pub jaccard: Vec<Vec<f64>>,
+    /// Average-linkage clustering on cosine distance, closest first.
+    pub dendrogram: Vec<Merge>,
+}
+
+impl SimilarityMatrix {
+    /// Compares every pair of books by their word frequencies. Document
+    /// frequencies for TF-IDF are taken within this set of books;
+    /// stopwords are left out.
+    pub fn new(book_ids: Vec<i64>, word_maps: &[&HashMap<String, u32>]) -> Self {
+        let vectors = tf_idf_vectors(word_maps);
+        let n = word_maps.len();
+
+        let mut cosine = vec![vec![0.0; n]; n];
+        let mut jaccard_matrix = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            for j in i..n {
+                let similarity = if i == j && !vectors[i].is_empty() {
+                    1.0
+                } else {
+                    comparison::cosine(&vectors[i], &vectors[j])
+                };
+                cosine[i][j] = similarity;
+                cosine[j][i] = similarity;
+
+                let overlap = jaccard(word_maps[i], word_maps[j]);
+                jaccard_matrix[i][j] = overlap;
+                jaccard_matrix[j][i] = overlap;
+            }
+        }
+
+        let distances: Vec<Vec<f64>> = cosine
+            .iter()
+            .map(|row| row.iter().map(|similarity| 1.0 - similarity).collect())
+            .collect();
+
+        Self {
+            book_ids,
+            dendrogram: average_linkage(&distances),
+            cosine,
+            jaccard: jaccard_matrix,
+        }
+    }
+}
+
+/// TF-IDF weights with the smoothed idf `ln((1 + n) / (1 + df)) + 1`, so
+/// that words shared by every book still count a little.
+fn tf_idf_vectors<'a>(word_maps: &[&'a HashMap<String, u32>]) -> Vec<HashMap<&'a str, f64>> {
+    let mut document_frequencies: HashMap<&str, u32> = HashMap::new();
+    for word_map in word_maps {
+        for word in word_map.keys().filter(|word| !is_stopword(word)) {
+            *document_frequencies.entry(word.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let n = word_maps.len() as f64;
+    word_maps
+        .iter()
+        .map(|word_map| {
+            word_map
+                .iter()
+                .filter(|(word, _)| !is_stopword(word))
+                .map(|(word, &count)| {
+                    let df = document_frequencies[word.as_str()] as f64;
+                    let idf = ((1.0 + n) / (1.0 + df)).ln() + 1.0;
+                    (word.as_str(), count as f64 * idf)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// UPGMA: repeatedly merges the two clusters with the smallest average
+/// distance between their members.
+fn average_linkage(distances: &[Vec<f64>]) -> Vec<Merge> {
+    let n = distances.len();
+    // (cluster id, member books) of the clusters not merged yet.
+    let mut clusters: Vec<(usize, Vec<usize>)> = (0..n).map(|book| (book, vec![book])).collect();
+    let mut merges = Vec::new();
+
+    while clusters.len() > 1 {
+        let mut closest = (0, 1, f64::INFINITY);
+        for i in 0..clusters.len() {
+            for j in i + 1..clusters.len() {
+                let (a, b) = (&clusters[i].1, &clusters[j].1);
+                let total: f64 = a
+                    .iter()
+                    .flat_map(|&x| b.iter().map(move |&y| distances[x][y]))
+                    .sum();
+                let average = total / (a.len() * b.len()) as f64;
+                if average < closest.2 {
+                    closest = (i, j, average);
+                }
+            }
+        }
+
+        let (i, j, distance) = closest;
+        let (right_id, right_members) = clusters.remove(j);
+        let (left_id, mut members) = clusters.remove(i);
+        members.extend(right_members);
+
+        merges.push(Merge {
+            left: left_id,
+            right: right_id,
+            distance,
+            size: members.len(),
+        });
+        clusters.push((n + merges.len() - 1, members));
+    }
+
+    merges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::count_words;
+
+    #[test]
+    fn test_matrix_is_symmetric() {
+        let books = [
+            count_words("whale whale sea ship ahab"),
+            count_words("whale sea ship ahab ahab"),
+            count_words("love marriage ball letter"),
+        ];
+        let maps: Vec<&HashMap<String, u32>> = books.iter().collect();
+
+        let matrix = SimilarityMatrix::new(vec![1, 2, 3], &maps);
+
+        for i in 0..3 {
+            assert_eq!(matrix.cosine[i][i], 1.0);
+            assert_eq!(matrix.jaccard[i][i], 1.0);
+            for j in 0..3 {
+                assert_eq!(matrix.cosine[i][j], matrix.cosine[j][i]);
+            }
+        }
+        assert!(matrix.cosine[0][1] > 0.5);
+        assert_eq!(matrix.cosine[0][2], 0.0);
+        assert!(matrix.cosine[0][2].is_sign_positive());
+        assert_eq!(matrix.jaccard[0][1], 1.0);
+    }
+
+    #[test]
+    fn test_dendrogram_joins_similar_books_first() {
+        let books = [
+            count_words("whale whale sea ship"),
+            count_words("love marriage ball"),
+            count_words("whale sea sea ship"),
+            count_words("love love marriage letter"),
+        ];
+        let maps: Vec<&HashMap<String, u32>> = books.iter().collect();
+
+        let dendrogram = SimilarityMatrix::new(vec![1, 2, 3, 4], &maps).dendrogram;
+
+        assert_eq!(dendrogram.len(), 3);
+        let pairs: Vec<(usize, usize)> = dendrogram[..2]
+            .iter()
+            .map(|merge| (merge.left, merge.right))
+            .collect();
+        assert!(pairs.contains(&(0, 2)) && pairs.contains(&(1, 3)));
+        assert_eq!(dendrogram[2].size, 4);
+        assert!(dendrogram[2].distance > dendrogram[1].distance);
+        assert_eq!((dendrogram[2].left, dendrogram[2].right), (4, 5));
+    }
+
+    #[test]
+    fn test_average_linkage() {
+        let distances = vec![
+            vec![0.0, 0.2, 0.9],
+            vec![0.2, 0.0, 0.7],
+            vec![0.9, 0.7, 0.0],
+        ];
+
+        let merges = average_linkage(&distances);
+
+        assert_eq!(
+            merges,
+            vec![
+                Merge {
+                    left: 0,
+                    right: 1,
+                    distance: 0.2,
+                    size: 2
+                },
+                Merge {
+                    left: 2,
+                    right: 3,
+                    distance: 0.8,
+                    size: 3
+                },
+            ]
+        );
+    }
+}
@@ -157,3 +157,10 @@ pub fn is_stopword(word: &str) -> bool {
     let word = word.to_lowercase();
     STOP_WORDS.contains(word.as_str())
 }
+
+/// Every stopword, in alphabetical order.
+pub fn stop_words() -> Vec<&'static str> {
+    let mut words: Vec<&'static str> = STOP_WORDS.iter().copied().collect();
+    words.sort_unstable();
+    words
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stop_words::stop_words;
+use crate::utils::mean;
+
+/// Relative frequencies of function words, the stopwords authors use
+/// without thinking about them.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct StyleProfile {
+    pub token_count: u64,
+    /// Occurrences per token of each function word the text uses.
+    pub frequencies: HashMap<String, f64>,
+}
+
+impl StyleProfile {
+    /// Profile of the combined word counts of one or more texts.
+    pub fn new(word_maps: &[&HashMap<String, u32>]) -> Self {
+        let token_count: u64 = word_maps
+            .iter()
+            .flat_map(|word_map| word_map.values())
+            .map(|&count| count as u64)
+            .sum();
+        let counts: HashMap<String, u64> = stop_words()
+            .into_iter()
+            .map(|word| {
+                let count = word_maps
+                    .iter()
+                    .filter_map(|word_map| word_map.get(word))
+                    .map(|&count| count as u64)
+                    .sum();
+                (word.to_string(), count)
+            })
+            .collect();
+
+        Self::from_counts(token_count, &counts)
+    }
+
+    /// Profile of `token_count` tokens with the function word counts in
+    /// `counts`, for texts whose full word counts are not at hand. Other
+    /// words in `counts` are ignored.
+    pub fn from_counts(token_count: u64, counts: &HashMap<String, u64>) -> Self {
+        if token_count == 0 {
+            return Self::default();
+        }
+
+        let frequencies = stop_words()
+            .into_iter()
+            .filter_map(|word| {
+                let count = counts.get(word).copied().unwrap_or(0);
+                (count > 0).then(|| (word.to_string(), count as f64 / token_count as f64))
+            })
+            .collect();
+
+        Self {
+            token_count,
+            frequencies,
+        }
+    }
+
+    fn frequency(&self, word: &str) -> f64 {
+        self.frequencies.get(word).copied().unwrap_or(0.0)
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct AuthorStyle {
+    pub author_id: i32,
+    pub author_name: String,
+    pub book_count: usize,
+    pub profile: StyleProfile,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct AuthorDelta {
+    pub author_id: i32,
+    pub author_name: String,
+    /// Burrows' Delta to the text; lower is closer.
+    pub delta: f64,
+}
+
+/// Fewest candidates [`Attribution::new`] can rank. Their profiles are the
+/// only reference for the z-scores, and with two of them every z-score is
+/// plus or minus one, so Delta just counts the words the text leans
+/// towards each author on.
+pub const MIN_CANDIDATES: usize = 3;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Attribution {
+    /// Function words the distances were measured on.
+    pub features: Vec<String>,
+    /// Candidate authors, most likely first.
+    pub candidates: Vec<AuthorDelta>,
+}
+
+impl Attribution {
+    /// Ranks `authors` by Burrows' Delta to `text`: the mean absolute
+    /// difference of function word z-scores, standardized over the
+    /// candidates' profiles. Words used equally by every candidate cannot
+    /// tell them apart and are left out. Needs at least [`MIN_CANDIDATES`]
+    /// authors to mean anything.
+    pub fn new(text: &StyleProfile, authors: &[AuthorStyle]) -> Self {
+        let statistics: Vec<(&str, f64, f64)> = stop_words()
+            .into_iter()
+            .filter_map(|word| {
+                let frequencies: Vec<f64> = authors
+                    .iter()
+                    .map(|author| author.profile.frequency(word))
+                    .collect();
+                let average = mean(frequencies.iter().copied());
+                let deviation =
+                    mean(frequencies.iter().map(|f| (f - average) * (f - average))).sqrt();
+                (deviation > 0.0).then_some((word, average, deviation))
+            })
+            .collect();
+
+        let mut candidates: Vec<AuthorDelta> = authors
+            .iter()
+            .map(|author| AuthorDelta {
+                author_id: author.author_id,
+                author_name: author.author_name.clone(),
+                delta: mean(statistics.iter().map(|&(word, average, deviation)| {
+                    let z_text = (text.frequency(word) - average) / deviation;
+                    let z_author = (author.profile.frequency(word) - average) / deviation;
+                    (z_text - z_author).abs()
+                })),
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            a.delta
+                .total_cmp(&b.delta)
+                .then_with(|| a.author_id.cmp(&b.author_id))
+        });
+
+        Self {
+            features: statistics
+                .into_iter()
+                .map(|(word, _, _)| word.to_string())
+                .collect(),
+            candidates,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::count_words;
+
+    fn author(author_id: i32, text: &str) -> AuthorStyle {
+        AuthorStyle {
+            author_id,
+            author_name: format!("Author {author_id}"),
+            book_count: 1,
+            profile: StyleProfile::new(&[&count_words(text)]),
+        }
+    }
+
+    #[test]
+    fn test_profile_counts_function_words() {
+        let a = count_words("the whale and the sea");
+        let b = count_words("the ship");
+
+        let profile = StyleProfile::new(&[&a, &b]);
+
+        assert_eq!(profile.token_count, 7);
+        assert_eq!(profile.frequencies["the"], 3.0 / 7.0);
+        assert_eq!(profile.frequencies["and"], 1.0 / 7.0);
+        assert!(!profile.frequencies.contains_key("whale"));
+        assert_eq!(StyleProfile::new(&[]), StyleProfile::default());
+
+        let counts = HashMap::from([
+            ("the".to_string(), 3),
+            ("and".to_string(), 1),
+            ("whale".to_string(), 1),
+        ]);
+        assert_eq!(StyleProfile::from_counts(7, &counts), profile);
+    }
+
+    #[test]
+    fn test_attribution_ranks_closest_style_first() {
+        let authors = [
+            author(1, "the the the of of whale and ship upon the sea"),
+            author(2, "i my my me i she her her was was love"),
+            author(3, "and and and but but to to ship then so"),
+        ];
+        let text = StyleProfile::new(&[&count_words("my i me her she was was my heart")]);
+
+        let attribution = Attribution::new(&text, &authors);
+
+        let ranking: Vec<i32> = attribution
+            .candidates
+            .iter()
+            .map(|candidate| candidate.author_id)
+            .collect();
+        assert_eq!(ranking[0], 2);
+        assert!(attribution.candidates[0].delta < attribution.candidates[1].delta);
+        assert!(attribution.features.contains(&"the".to_string()));
+        // No candidate uses "into", so it cannot separate them.
+        assert!(!attribution.features.contains(&"into".to_string()));
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::book::tokenize;
+use crate::concordance::stem;
+use crate::segmentation::{paragraphs, sentences};
+use crate::stop_words::is_stopword;
+use crate::utils::pagerank;
+
+/// Sentences shorter or longer than this, in tokens, do not make a blurb.
+const MIN_SENTENCE_TOKENS: usize = 6;
+const MAX_SENTENCE_TOKENS: usize = 60;
+/// Longer books are ranked on this many sentences spread evenly over the
+/// text, keeping the quadratic similarity graph affordable.
+const MAX_CANDIDATES: usize = 2000;
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct SummarySentence {
+    /// Position among all sentences of the text.
+    pub position: usize,
+    pub text: String,
+    pub score: f64,
+}
+
+/// Extractive summary: the best-connected sentences, in reading order.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct Summary {
+    pub sentences: Vec<SummarySentence>,
+}
+
+impl Summary {
+    /// Mihalcea and Tarau's TextRank: ranks sentences by PageRank over a
+    /// graph weighted by shared word stems, normalized for sentence length,
+    /// and keeps the `k` best.
+    pub fn new(content: &str, k: usize) -> Self {
+        let all: Vec<String> = paragraphs(content)
+            .iter()
+            .flat_map(|paragraph| sentences(paragraph).into_iter().map(str::to_string))
+            .collect();
+
+        let mut vocabulary: HashMap<String, u32> = HashMap::new();
+        let mut candidates: Vec<(usize, Vec<u32>)> = all
+            .iter()
+            .enumerate()
+            .filter_map(|(position, sentence)| {
+                let tokens: Vec<String> = tokenize(sentence).collect();
+                if !(MIN_SENTENCE_TOKENS..=MAX_SENTENCE_TOKENS).contains(&tokens.len()) {
+                    return None;
+                }
+                let mut stems: Vec<u32> = tokens
+                    .iter()
+                    .filter(|token| !is_stopword(token))
+                    .map(|token| {
+                        let next = vocabulary.len() as u32;
+                        *vocabulary.entry(stem(token)).or_insert(next)
+                    })
+                    .collect();
+                stems.sort_unstable();
+                stems.dedup();
+                Some((position, stems))
+            })
+            .collect();
+
+        if candidates.len() > MAX_CANDIDATES {
+            let step = candidates.len() as f64 / MAX_CANDIDATES as f64;
+            candidates = (0..MAX_CANDIDATES)
+                .map(|i| candidates[(i as f64 * step) as usize].clone())
+                .collect();
+        }
+
+        let stems: Vec<&[u32]> = candidates.iter().map(|(_, s)| s.as_slice()).collect();
+        let scores = text_rank(&stems);
+
+        let mut ranked: Vec<SummarySentence> = candidates
+            .iter()
+            .zip(scores)
+            .map(|(&(position, _), score)| SummarySentence {
+                position,
+                text: all[position].clone(),
+                score,
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.position.cmp(&b.position))
+        });
+        ranked.truncate(k);
+        ranked.sort_by_key(|sentence| sentence.position);
+
+        Self { sentences: ranked }
+    }
+
+    /// The `k` best sentences of this summary, still in reading order.
+    pub fn top(&self, k: usize) -> Self {
+        let mut sentences = self.sentences.clone();
+        sentences.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.position.cmp(&b.position))
+        });
+        sentences.truncate(k);
+        sentences.sort_by_key(|sentence| sentence.position);
+
+        Self { sentences }
+    }
+}
+
+/// Weighted PageRank over sentences, where two sentences are linked by
+/// their shared stems over the sum of the logs of their lengths.
+fn text_rank(sentences: &[&[u32]]) -> Vec<f64> {
+    let n = sentences.len();
+    let mut edges = vec![Vec::new(); n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let length = (sentences[i].len() as f64).ln() + (sentences[j].len() as f64).ln();
+            if length <= 0.0 {
+                continue;
+            }
+            let weight = overlap(sentences[i], sentences[j]) as f64 / length;
+            if weight > 0.0 {
+                edges[i].push((j, weight));
+                edges[j].push((i, weight));
+            }
+        }
+    }
+    pagerank(&edges)
+}
+
+/// Number of values two sorted, deduplicated slices share.
+fn overlap(a: &[u32], b: &[u32]) -> usize {
+    let (mut i, mut j, mut shared) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                shared += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    shared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+The whale rose from the sea beside the ship at dawn.
+
+Ishmael watched the great whale circle the ship in the grey sea.
+The cook burned the bread again that morning in the galley.
+
+Ahab swore the whale would die before the ship left that sea.
+Short one.";
+
+    #[test]
+    fn test_summary_keeps_central_sentences_in_order() {
+        let summary = Summary::new(TEXT, 2);
+
+        let positions: Vec<usize> = summary.sentences.iter().map(|s| s.position).collect();
+        assert_eq!(summary.sentences.len(), 2);
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(!summary
+            .sentences
+            .iter()
+            .any(|sentence| sentence.text.contains("bread")));
+        assert!(!positions.contains(&4));
+    }
+
+    #[test]
+    fn test_top_narrows_cached_summary() {
+        let summary = Summary::new(TEXT, 10);
+        assert_eq!(summary.sentences.len(), 4);
+
+        let top = summary.top(2);
+
+        assert_eq!(top, Summary::new(TEXT, 2));
+        assert_eq!(Summary::new("", 3), Summary::default());
+    }
+
+    #[test]
+    fn test_overlap() {
+        assert_eq!(overlap(&[1, 3, 5, 7], &[2, 3, 7, 9]), 2);
+        assert_eq!(overlap(&[], &[1]), 0);
+    }
+}
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::keyness::CorpusFrequencies;
+use crate::stop_words::is_stopword;
+
+/// Corpora of at least this many documents drop words found in only one
+/// document or in most of them, which cannot make up a shared theme.
+const MIN_FILTERED_DOCUMENTS: u32 = 5;
+const MAX_DOCUMENT_SHARE: f64 = 0.8;
+/// Keeps the multiplicative updates away from dividing by zero.
+const EPSILON: f64 = 1e-10;
+
+/// The words topics are modeled over, each with its inverse document
+/// frequency.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default, Debug)]
+pub struct Vocabulary {
+    pub words: Vec<String>,
+    idf: Vec<f64>,
+    index: HashMap<String, usize>,
+}
+
+impl Vocabulary {
+    /// The `size` non-stopwords found in the most documents of `corpus`.
+    pub fn new(corpus: &CorpusFrequencies, size: usize) -> Self {
+        let documents = corpus.document_count;
+        let mut candidates: Vec<(&String, u32)> = corpus
+            .document_frequencies
+            .iter()
+            .filter(|(word, _)| !is_stopword(word))
+            .filter(|&(_, &df)| {
+                documents < MIN_FILTERED_DOCUMENTS
+                    || (df >= 2 && df as f64 <= MAX_DOCUMENT_SHARE * documents as f64)
+            })
+            .map(|(word, &df)| (word, df))
+            .collect();
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        candidates.truncate(size);
+
+        let mut vocabulary = Self::default();
+        for (position, (word, df)) in candidates.into_iter().enumerate() {
+            vocabulary
+                .idf
+                .push(((1.0 + documents as f64) / (1.0 + df as f64)).ln() + 1.0);
+            vocabulary.index.insert(word.clone(), position);
+            vocabulary.words.push(word.clone());
+        }
+        vocabulary
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// A book as a sparse row of dampened TF-IDF weights over the
+    /// vocabulary, scaled to unit length so that long books do not
+    /// dominate the topics.
+    pub fn document(&self, word_map: &HashMap<String, u32>) -> Vec<(usize, f64)> {
+        let mut row: Vec<(usize, f64)> = word_map
+            .iter()
+            .filter(|(_, &count)| count > 0)
+            .filter_map(|(word, &count)| {
+                let position = *self.index.get(word)?;
+                Some((position, (1.0 + (count as f64).ln()) * self.idf[position]))
+            })
+            .collect();
+        row.sort_unstable_by_key(|&(position, _)| position);
+
+        let norm = row.iter().map(|(_, x)| x * x).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            row.iter_mut().for_each(|(_, x)| *x /= norm);
+        }
+        row
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct TopicWord {
+    pub word: String,
+    pub weight: f64,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct TopicSummary {
+    pub topic_id: i32,
+    pub words: Vec<TopicWord>,
+}
+
+/// A topic's share of a book.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct BookTopic {
+    pub topic_id: i32,
+    pub weight: f64,
+    pub words: Vec<TopicWord>,
+}
+
+/// Non-negative matrix factorization of the document-word matrix into
+/// document-topic and topic-word weights.
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct TopicModel {
+    pub vocabulary: Vec<String>,
+    /// Each topic's distribution over the vocabulary.
+    pub topic_words: Vec<Vec<f64>>,
+    /// Each document's distribution over the topics, all zeros for a
+    /// document without vocabulary words.
+    pub document_topics: Vec<Vec<f64>>,
+}
+
+impl TopicModel {
+    /// Fits `topics` topics to `documents`, rows made by
+    /// [`Vocabulary::document`], with Lee and Seung's multiplicative
+    /// updates. The starting point is fixed, so the same input always gives
+    /// the same topics.
+    pub fn fit(
+        vocabulary: &Vocabulary,
+        documents: &[Vec<(usize, f64)>],
+        topics: usize,
+        iterations: usize,
+    ) -> Self {
+        let (n, v, k) = (documents.len(), vocabulary.len(), topics);
+        let mut random = XorShift(0x9e3779b97f4a7c15);
+        let nonzeros: usize = documents.iter().map(Vec::len).sum();
+        let total: f64 = documents.iter().flatten().map(|(_, x)| x).sum();
+        let scale = if nonzeros > 0 {
+            (total / nonzeros as f64 / k.max(1) as f64).sqrt()
+        } else {
+            1.0
+        };
+
+        let mut w: Vec<Vec<f64>> = (0..n)
+            .map(|_| (0..k).map(|_| scale * random.next()).collect())
+            .collect();
+        let mut h: Vec<Vec<f64>> = (0..k)
+            .map(|_| (0..v).map(|_| scale * random.next()).collect())
+            .collect();
+
+        for _ in 0..iterations {
+            // H <- H * (W^T X) / (W^T W H)
+            let mut wt_x = vec![vec![0.0; v]; k];
+            for (row, document) in w.iter().zip(documents) {
+                for &(word, x) in document {
+                    for t in 0..k {
+                        wt_x[t][word] += row[t] * x;
+                    }
+                }
+            }
+            let wt_w = gram(&w, k);
+            for t in 0..k {
+                for word in 0..v {
+                    let denominator: f64 = (0..k).map(|s| wt_w[t][s] * h[s][word]).sum();
+                    h[t][word] *= wt_x[t][word] / (denominator + EPSILON);
+                }
+            }
+
+            // W <- W * (X H^T) / (W H H^T)
+            let h_ht: Vec<Vec<f64>> = (0..k)
+                .map(|t| {
+                    (0..k)
+                        .map(|s| h[t].iter().zip(&h[s]).map(|(a, b)| a * b).sum())
+                        .collect()
+                })
+                .collect();
+            for (row, document) in w.iter_mut().zip(documents) {
+                let mut x_ht = vec![0.0; k];
+                for &(word, x) in document {
+                    for t in 0..k {
+                        x_ht[t] += x * h[t][word];
+                    }
+                }
+                let current = row.clone();
+                for t in 0..k {
+                    let denominator: f64 = (0..k).map(|s| current[s] * h_ht[s][t]).sum();
+                    row[t] *= x_ht[t] / (denominator + EPSILON);
+                }
+            }
+        }
+
+        for (row, document) in w.iter_mut().zip(documents) {
+            if document.is_empty() {
+                row.iter_mut().for_each(|x| *x = 0.0);
+            }
+        }
+        h.iter_mut().for_each(|row| normalize(row));
+        w.iter_mut().for_each(|row| normalize(row));
+
+        Self {
+            vocabulary: vocabulary.words.clone(),
+            topic_words: h,
+            document_topics: w,
+        }
+    }
+
+    /// Every topic with its `limit` heaviest words.
+    pub fn topics(&self, limit: usize) -> Vec<TopicSummary> {
+        self.topic_words
+            .iter()
+            .enumerate()
+            .map(|(topic, weights)| {
+                let mut words: Vec<TopicWord> = self
+                    .vocabulary
+                    .iter()
+                    .zip(weights)
+                    .map(|(word, &weight)| TopicWord {
+                        word: word.clone(),
+                        weight,
+                    })
+                    .collect();
+                words.sort_by(|a, b| {
+                    b.weight
+                        .total_cmp(&a.weight)
+                        .then_with(|| a.word.cmp(&b.word))
+                });
+                words.truncate(limit);
+                TopicSummary {
+                    topic_id: topic as i32,
+                    words,
+                }
+            })
+            .collect()
+    }
+}
+
+/// `W^T W` for the `k` columns of `w`.
+fn gram(w: &[Vec<f64>], k: usize) -> Vec<Vec<f64>> {
+    let mut product = vec![vec![0.0; k]; k];
+    for row in w {
+        for t in 0..k {
+            for s in 0..k {
+                product[t][s] += row[t] * row[s];
+            }
+        }
+    }
+    product
+}
+
+/// Scales `row` to sum to one, unless it is all zeros.
+fn normalize(row: &mut [f64]) {
+    let sum: f64 = row.iter().sum();
+    if sum > 0.0 {
+        row.iter_mut().for_each(|x| *x /= sum);
+    }
+}
+
+/// Marsaglia's xorshift, enough for a reproducible starting point.
+struct XorShift(u64);
+
+impl XorShift {
+    /// Uniform in (0, 1].
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        ((self.0 >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::count_words;
+
+    #[test]
+    fn test_vocabulary_filters_rare_and_common_words() {
+        let books: Vec<HashMap<String, u32>> = [
+            "whale sea ship the",
+            "whale sea love the",
+            "whale sea ship ahab",
+            "whale love ball",
+            "whale love ball letter",
+        ]
+        .iter()
+        .map(|text| count_words(text))
+        .collect();
+
+        let vocabulary = Vocabulary::new(&CorpusFrequencies::from_books(&books), 10);
+
+        // "whale" is in every book, "ahab" and "letter" in only one.
+        assert_eq!(vocabulary.words, vec!["love", "sea", "ball", "ship"]);
+        let row = vocabulary.document(&books[0]);
+        assert_eq!(
+            row.iter().map(|(word, _)| *word).collect::<Vec<_>>(),
+            [1, 3]
+        );
+        let norm: f64 = row.iter().map(|(_, x)| x * x).sum();
+        assert!((norm - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fit_separates_themes() {
+        let books: Vec<HashMap<String, u32>> = [
+            "whale sea ship harpoon whale sea",
+            "sea ship harpoon sailor whale",
+            "whale harpoon sailor ship",
+            "love marriage ball letter love",
+            "marriage ball letter sister love",
+            "love sister letter marriage",
+        ]
+        .iter()
+        .map(|text| count_words(text))
+        .collect();
+        let vocabulary = Vocabulary::new(&CorpusFrequencies::from_books(&books), 100);
+        let documents: Vec<_> = books.iter().map(|b| vocabulary.document(b)).collect();
+
+        let model = TopicModel::fit(&vocabulary, &documents, 2, 200);
+
+        let dominant = |document: &Vec<f64>| if document[0] > document[1] { 0 } else { 1 };
+        let sea = dominant(&model.document_topics[0]);
+        assert!(model.document_topics[..3]
+            .iter()
+            .all(|document| dominant(document) == sea));
+        assert!(model.document_topics[3..]
+            .iter()
+            .all(|document| dominant(document) != sea));
+
+        let topics = model.topics(3);
+        let sea_words: Vec<&str> = topics[sea].words.iter().map(|w| w.word.as_str()).collect();
+        assert!(sea_words.contains(&"harpoon"));
+        for weights in &model.topic_words {
+            assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_fit_is_reproducible() {
+        let books = [
+            count_words("whale sea ship"),
+            count_words("love ball letter"),
+        ];
+        let vocabulary = Vocabulary::new(&CorpusFrequencies::from_books(&books), 10);
+        let documents: Vec<_> = books.iter().map(|b| vocabulary.document(b)).collect();
+
+        let empty = vec![Vec::new()];
+        assert_eq!(
+            TopicModel::fit(&vocabulary, &documents, 2, 20),
+            TopicModel::fit(&vocabulary, &documents, 2, 20)
+        );
+        assert_eq!(
+            TopicModel::fit(&vocabulary, &empty, 2, 20).document_topics,
+            vec![vec![0.0, 0.0]]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 pub fn add_to_vec<T: PartialEq + Clone>(vec_option: &mut Option<Vec<T>>, item: T) {
     match vec_option {
         Some(vec) => {
-            if !vec.iter().any(|i| *i == item) {
+            if !vec.contains(&item) {
                 vec.push(item);
             }
         }
@@ -9,6 +9,58 @@ pub fn add_to_vec<T: PartialEq + Clone>(vec_option: &mut Option<Vec<T>>, item: T
     }
 }
 
+/// Arithmetic mean, zero for no values.
+pub fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f64
+    }
+}
+
+/// 64-bit FNV-1a, for hashes that must stay the same across builds and
+/// Rust releases, unlike those of the standard hasher.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+const TOLERANCE: f64 = 1e-6;
+
+/// PageRank over an undirected weighted graph, given as the `(neighbour,
+/// weight)` edges of each node. A node passes its rank on in proportion to
+/// the weights of its edges.
+pub fn pagerank(edges: &[Vec<(usize, f64)>]) -> Vec<f64> {
+    let totals: Vec<f64> = edges
+        .iter()
+        .map(|node| node.iter().map(|&(_, weight)| weight).sum())
+        .collect();
+
+    let mut ranks = vec![1.0; edges.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let next: Vec<f64> = edges
+            .iter()
+            .map(|node| {
+                let incoming: f64 = node
+                    .iter()
+                    .map(|&(j, weight)| weight / totals[j] * ranks[j])
+                    .sum();
+                1.0 - DAMPING + DAMPING * incoming
+            })
+            .collect();
+        let change: f64 = next.iter().zip(&ranks).map(|(a, b)| (a - b).abs()).sum();
+        ranks = next;
+        if change < TOLERANCE {
+            break;
+        }
+    }
+    ranks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -33,4 +85,37 @@ mod tests {
         add_to_vec(&mut vec_option, 8);
         assert_eq!(vec_option, Some(vec![5, 6, 7, 8]));
     }
+
+    #[test]
+    fn test_mean() {
+        assert_eq!(mean([1.0, 2.0, 6.0].into_iter()), 3.0);
+        assert_eq!(mean(std::iter::empty()), 0.0);
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        // Reference values from the FNV authors' test suite.
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn test_pagerank() {
+        // A star: the centre collects the rank of every leaf.
+        let star = vec![
+            vec![(1, 1.0), (2, 1.0), (3, 1.0)],
+            vec![(0, 1.0)],
+            vec![(0, 1.0)],
+            vec![(0, 1.0)],
+        ];
+        let ranks = pagerank(&star);
+        assert!(ranks[0] > ranks[1]);
+        assert!((ranks.iter().sum::<f64>() - 4.0).abs() < 1e-5);
+
+        // A heavier edge passes on more rank.
+        let ranks = pagerank(&[vec![(1, 1.0), (2, 3.0)], vec![(0, 1.0)], vec![(0, 3.0)]]);
+        assert!(ranks[2] > ranks[1]);
+        assert_eq!(pagerank(&[vec![]]), vec![1.0 - DAMPING]);
+    }
 }
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::keyness::CorpusFrequencies;
+use crate::stop_words::is_stopword;
+use crate::utils::fnv1a;
+
+/// Length of every content vector.
+pub const VECTOR_DIMENSIONS: usize = 256;
+
+/// Reduces a book's TF-IDF weights to [`VECTOR_DIMENSIONS`] with the
+/// hashing trick: each word adds its weight to one dimension, with a sign
+/// also taken from its hash, so collisions tend to cancel out. Term
+/// frequencies are dampened logarithmically and stopwords are left out.
+/// The result has unit length, or is all zeros for a book without words.
+pub fn content_vector(word_map: &HashMap<String, u32>, corpus: &CorpusFrequencies) -> Vec<f32> {
+    let documents = corpus.document_count as f64;
+    let mut vector = vec![0.0f64; VECTOR_DIMENSIONS];
+
+    for (word, &count) in word_map {
+        if count == 0 || is_stopword(word) {
+            continue;
+        }
+        let document_frequency = corpus.document_frequencies.get(word).copied().unwrap_or(0);
+        let idf = ((1.0 + documents) / (1.0 + document_frequency as f64)).ln() + 1.0;
+        let weight = (1.0 + (count as f64).ln()) * idf;
+
+        let hash = fnv1a(word.as_bytes());
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[(hash % VECTOR_DIMENSIONS as u64) as usize] += sign * weight;
+    }
+
+    let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+    vector
+        .into_iter()
+        .map(|x| if norm > 0.0 { (x / norm) as f32 } else { 0.0 })
+        .collect()
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Neighbour {
+    pub book_id: i64,
+    /// Cosine similarity of the two content vectors.
+    pub similarity: f32,
+}
+
+/// Exact nearest-neighbour search over unit-length content vectors, kept
+/// in one contiguous buffer so a query is a single linear scan.
+#[derive(Clone, Default, Debug)]
+pub struct VectorIndex {
+    book_ids: Vec<i64>,
+    positions: HashMap<i64, usize>,
+    vectors: Vec<f32>,
+}
+
+impl VectorIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.book_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.book_ids.is_empty()
+    }
+
+    /// Adds or replaces a book's vector. Vectors of the wrong length are
+    /// ignored.
+    pub fn insert(&mut self, book_id: i64, vector: &[f32]) {
+        if vector.len() != VECTOR_DIMENSIONS {
+            return;
+        }
+        match self.positions.get(&book_id) {
+            Some(&position) => {
+                let start = position * VECTOR_DIMENSIONS;
+                self.vectors[start..start + VECTOR_DIMENSIONS].copy_from_slice(vector);
+            }
+            None => {
+                self.positions.insert(book_id, self.book_ids.len());
+                self.book_ids.push(book_id);
+                self.vectors.extend_from_slice(vector);
+            }
+        }
+    }
+
+    pub fn get(&self, book_id: i64) -> Option<&[f32]> {
+        let start = self.positions.get(&book_id)? * VECTOR_DIMENSIONS;
+        Some(&self.vectors[start..start + VECTOR_DIMENSIONS])
+    }
+
+    /// Every indexed book with its vector, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, &[f32])> + '_ {
+        self.book_ids
+            .iter()
+            .copied()
+            .zip(self.vectors.chunks_exact(VECTOR_DIMENSIONS))
+    }
+
+    /// The `k` books most similar to `book_id`, excluding itself. Empty if
+    /// the book is not indexed.
+    pub fn nearest(&self, book_id: i64, k: usize) -> Vec<Neighbour> {
+        let query = match self.get(book_id) {
+            Some(query) => query,
+            None => return Vec::new(),
+        };
+
+        let mut neighbours: Vec<Neighbour> = self
+            .iter()
+            .filter(|&(id, _)| id != book_id)
+            .map(|(id, vector)| Neighbour {
+                book_id: id,
+                similarity: query.iter().zip(vector).map(|(a, b)| a * b).sum(),
+            })
+            .collect();
+
+        if neighbours.len() > k {
+            neighbours.select_nth_unstable_by(k, |a, b| b.similarity.total_cmp(&a.similarity));
+            neighbours.truncate(k);
+        }
+        neighbours.sort_by(|a, b| {
+            b.similarity
+                .total_cmp(&a.similarity)
+                .then_with(|| a.book_id.cmp(&b.book_id))
+        });
+        neighbours
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::count_words;
+
+    #[test]
+    fn test_content_vector_is_unit_length() {
+        let book = count_words("the whale and the sea and the ship");
+        let corpus = CorpusFrequencies::from_books(std::slice::from_ref(&book));
+
+        let vector = content_vector(&book, &corpus);
+
+        assert_eq!(vector.len(), VECTOR_DIMENSIONS);
+        let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+        assert_eq!(
+            content_vector(&count_words("the and"), &corpus),
+            vec![0.0; 256]
+        );
+    }
+
+    #[test]
+    fn test_nearest_finds_similar_vocabulary() {
+        let books = [
+            count_words("whale harpoon ship sea captain whale ahab"),
+            count_words("ball marriage letter sister estate love"),
+            count_words("whale ship sea harpoon sailor captain"),
+            count_words("marriage love estate ball cousin sister"),
+        ];
+        let corpus = CorpusFrequencies::from_books(&books);
+
+        let mut index = VectorIndex::new();
+        for (id, book) in books.iter().enumerate() {
+            index.insert(id as i64, &content_vector(book, &corpus));
+        }
+
+        let neighbours = index.nearest(0, 2);
+        assert_eq!(neighbours.len(), 2);
+        assert_eq!(neighbours[0].book_id, 2);
+        assert!(neighbours[0].similarity > neighbours[1].similarity);
+        assert_eq!(index.nearest(3, 1)[0].book_id, 1);
+        assert!(index.nearest(42, 3).is_empty());
+    }
+
+    #[test]
+    fn test_insert_replaces_vector() {
+        let mut index = VectorIndex::new();
+        let mut vector = vec![0.0; VECTOR_DIMENSIONS];
+        vector[0] = 1.0;
+        index.insert(7, &vector);
+        vector[0] = 0.0;
+        vector[1] = 1.0;
+        index.insert(7, &vector);
+        index.insert(8, &[1.0]);
+
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.get(7).unwrap()[1], 1.0);
+    }
+}
@@ -0,0 +1,16 @@
+-- Word frequencies of every analyzed book, the basis for corpus-level
+-- document frequencies and keyness.
+CREATE TABLE analyzed_books (
+    book_id BIGINT PRIMARY KEY REFERENCES books,
+    token_count BIGINT NOT NULL,
+    analyzed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE book_word_counts (
+    book_id BIGINT NOT NULL REFERENCES analyzed_books ON DELETE CASCADE,
+    word TEXT NOT NULL,
+    count INT NOT NULL,
+    PRIMARY KEY (book_id, word)
+);
+
+CREATE INDEX book_word_counts_word_idx ON book_word_counts (word);
@@ -0,0 +1,6 @@
+-- Full analytics of books processed by the bulk analysis job.
+CREATE TABLE book_analytics (
+    book_id BIGINT PRIMARY KEY REFERENCES books,
+    analytics JSONB NOT NULL,
+    analyzed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
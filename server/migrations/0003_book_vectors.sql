@@ -0,0 +1,6 @@
+-- Content vectors of analyzed books for content-based recommendations.
+CREATE TABLE book_vectors (
+    book_id BIGINT PRIMARY KEY REFERENCES books,
+    vector REAL[] NOT NULL,
+    built_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,15 @@
+-- Topic model fitted by the topic job: each topic's weight over words and
+-- each book's weight over topics. Rebuilt as a whole on every run.
+CREATE TABLE topic_words (
+    topic_id INT NOT NULL,
+    word TEXT NOT NULL,
+    weight DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (topic_id, word)
+);
+
+CREATE TABLE book_topics (
+    book_id BIGINT NOT NULL REFERENCES books,
+    topic_id INT NOT NULL,
+    weight DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (book_id, topic_id)
+);
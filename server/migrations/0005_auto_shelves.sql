@@ -0,0 +1,15 @@
+-- Shelves generated by clustering books on their content vectors, kept
+-- apart from the curated bookshelves. Rebuilt as a whole on every run.
+CREATE TABLE auto_shelves (
+    shelf_id INT PRIMARY KEY,
+    shelf_name TEXT NOT NULL,
+    keywords TEXT[] NOT NULL,
+    count_of_books INT NOT NULL,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE books_auto_shelves (
+    book_id BIGINT PRIMARY KEY REFERENCES books,
+    shelf_id INT NOT NULL REFERENCES auto_shelves ON DELETE CASCADE,
+    similarity REAL NOT NULL
+);
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Finished,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Analysis,
+    Vectors,
+    Topics,
+    AutoShelves,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct JobStatus {
+    pub id: u64,
+    pub kind: JobKind,
+    pub state: JobState,
+    pub total: usize,
+    pub processed: usize,
+    pub failed: usize,
+    pub error: Option<String>,
+}
+
+/// Progress of background jobs, kept in memory for the server's lifetime.
+#[derive(Default)]
+pub struct Jobs {
+    next_id: AtomicU64,
+    statuses: Mutex<HashMap<u64, JobStatus>>,
+}
+
+impl Jobs {
+    /// Starts a job, unless one of the same kind is still running, since
+    /// both would write the same rows. The running job is returned instead.
+    pub fn start(&self, kind: JobKind, total: usize) -> Result<u64, JobStatus> {
+        let mut statuses = self.statuses.lock().unwrap();
+        if let Some(running) = statuses
+            .values()
+            .find(|status| status.kind == kind && status.state == JobState::Running)
+        {
+            return Err(running.clone());
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        statuses.insert(
+            id,
+            JobStatus {
+                id,
+                kind,
+                state: JobState::Running,
+                total,
+                processed: 0,
+                failed: 0,
+                error: None,
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn update(&self, id: u64, update: impl FnOnce(&mut JobStatus)) {
+        if let Some(status) = self.statuses.lock().unwrap().get_mut(&id) {
+            update(status);
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<JobStatus> {
+        self.statuses.lock().unwrap().get(&id).cloned()
+    }
+}
@@ -1,27 +1,47 @@
 use actix_cors::Cors;
-use actix_web::{web::Data, App, HttpServer};
+use actix_web::{
+    web::{Data, JsonConfig},
+    App, HttpServer,
+};
 use dotenv::dotenv;
+use jobs::Jobs;
 use services::{
-    get_book, get_books_from_author, get_books_from_bookshelf, get_books_of_subject,
-    get_top_bookshelves, get_top_subjects, get_top_ten_books,
+    attribute_authorship, compare_books, get_author_analytics, get_author_style, get_auto_shelves,
+    get_book, get_book_topics, get_books_from_author, get_books_from_auto_shelf,
+    get_books_from_bookshelf, get_books_of_subject, get_bookshelf_analytics,
+    get_chapter_keyphrases, get_chapters, get_character_network, get_characters, get_concordance,
+    get_dispersion, get_distinctive_words, get_job, get_keyphrases, get_sentiment,
+    get_similar_books, get_similarity_matrix, get_subject_analytics, get_summary, get_text,
+    get_top_bookshelves, get_top_subjects, get_top_ten_books, get_topics, start_analysis_job,
+    start_auto_shelf_job, start_topic_job, start_vector_job,
 };
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::RwLock;
 
+mod jobs;
 mod services;
 
-pub struct AppState {
-    db: Pool<Postgres>,
-}
-
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
+    env_logger::init_from_env(env_logger::Env::default().default_filter_or("warn"));
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let pool = PgPoolOptions::new()
         .max_connections(5)
         .connect(&database_url)
         .await
         .expect("Error building a connection pool");
+    sqlx::migrate!()
+        .run(&pool)
+        .await
+        .expect("Error running database migrations");
+
+    let jobs = Data::new(Jobs::default());
+    let vector_index = Data::new(RwLock::new(
+        services::load_vector_index(&pool)
+            .await
+            .expect("Error loading content vectors"),
+    ));
 
     HttpServer::new(move || {
         let cors = Cors::permissive();
@@ -29,10 +49,41 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .wrap(cors)
             .app_data(Data::new(pool.clone()))
+            .app_data(jobs.clone())
+            .app_data(vector_index.clone())
+            .app_data(JsonConfig::default().limit(services::MAX_JSON_SIZE))
             .service(get_top_ten_books)
             .service(get_book)
+            .service(get_concordance)
+            .service(get_dispersion)
+            .service(get_chapters)
+            .service(get_keyphrases)
+            .service(get_chapter_keyphrases)
+            .service(get_sentiment)
+            .service(get_characters)
+            .service(get_character_network)
+            .service(get_distinctive_words)
+            .service(get_text)
+            .service(get_similar_books)
+            .service(get_summary)
+            .service(get_book_topics)
+            .service(get_topics)
+            .service(compare_books)
+            .service(get_similarity_matrix)
+            .service(attribute_authorship)
+            .service(start_analysis_job)
+            .service(start_vector_job)
+            .service(start_topic_job)
+            .service(start_auto_shelf_job)
+            .service(get_job)
+            .service(get_subject_analytics)
+            .service(get_bookshelf_analytics)
+            .service(get_author_analytics)
+            .service(get_author_style)
             .service(get_top_subjects)
             .service(get_top_bookshelves)
+            .service(get_auto_shelves)
+            .service(get_books_from_auto_shelf)
             .service(get_books_from_bookshelf)
             .service(get_books_of_subject)
             .service(get_books_from_author)
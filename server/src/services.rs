@@ -1,10 +1,1369 @@
-use model::book::{Analytics, Author, Book, Bookshelf, Subject};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
 
-use actix_web::{get, web, HttpResponse, Responder};
+use model::book::{count_words, Analytics, Author, Book, Bookshelf, Subject};
+use model::builder::AnalyticsBuilder;
+use model::chapters::{split_chapters, Chapter};
+use model::characters::{cast, CharacterNetwork, CooccurrenceScope, GraphFormat};
+use model::clustering::{cluster_keywords, spherical_k_means, AutoShelf};
+use model::comparison::Comparison;
+use model::concordance::Concordance;
+use model::corpus::CorpusProfile;
+use model::dispersion::Dispersion;
+use model::gutenberg::{strip_boilerplate, BoilerplateStripper};
+use model::keyness::{CorpusFrequencies, DistinctiveWords, KeynessMeasure};
+use model::keyphrases::{KeyphraseMethod, Keyphrases};
+use model::ngrams::Ngrams;
+use model::parallel;
+use model::reader::{char_slice, paginate, reflow};
+use model::recommendations::{
+    MatchKind, MatchReason, RecommendationMode, SimilarBook, AUTHOR_WEIGHT, BOOKSHELF_WEIGHT,
+    LANGUAGE_WEIGHT, SUBJECT_WEIGHT,
+};
+use model::sentiment::SentimentArc;
+use model::similarity::SimilarityMatrix;
+use model::stop_words::stop_words;
+use model::stylometry::{Attribution, AuthorStyle, StyleProfile, MIN_CANDIDATES};
+use model::summary::Summary;
+use model::topics::{BookTopic, TopicModel, TopicSummary, TopicWord, Vocabulary};
+use model::utils::fnv1a;
+use model::vectors::{content_vector, VectorIndex};
+
+use actix_web::http::header::{
+    self, ContentRangeSpec, EntityTag, Header, IfNoneMatch, IfRange, Range,
+};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
 use sqlx::PgPool;
 
-#[get("/")]
-pub async fn get_top_ten_books(pool: web::Data<PgPool>) -> impl Responder {
+use crate::jobs::{JobKind, JobState, Jobs};
+
+const MAX_NGRAM_SIZE: usize = 5;
+const DEFAULT_NGRAM_LIMIT: usize = 25;
+const DEFAULT_CONCORDANCE_WINDOW: usize = 8;
+const MAX_CONCORDANCE_WINDOW: usize = 50;
+const DEFAULT_CONCORDANCE_LIMIT: usize = 50;
+const MAX_CONCORDANCE_LIMIT: usize = 1000;
+const DEFAULT_DISPERSION_SEGMENTS: usize = 10;
+const MAX_DISPERSION_SEGMENTS: usize = 1000;
+const DEFAULT_CHAPTER_TOP_WORDS: u32 = 10;
+const DEFAULT_DISTINCTIVE_LIMIT: usize = 25;
+const DEFAULT_SENTIMENT_SEGMENTS: usize = 100;
+const MAX_SENTIMENT_SEGMENTS: usize = 1000;
+/// Segments averaged into each point of the smoothed arc.
+const DEFAULT_SENTIMENT_WINDOW: usize = 9;
+/// Fewer mentions than this rarely make a character rather than a place
+/// or a stray capitalized word.
+const DEFAULT_MIN_MENTIONS: u32 = 5;
+const DEFAULT_COOCCURRENCE_WINDOW: usize = 15;
+const MAX_COOCCURRENCE_WINDOW: usize = 500;
+const DEFAULT_KEYPHRASE_LIMIT: usize = 20;
+const DEFAULT_CHAPTER_KEYPHRASES: usize = 5;
+const DEFAULT_AGGREGATE_TOP_WORDS: u32 = 25;
+/// Books downloaded and analyzed together by the bulk analysis job.
+const BULK_BATCH_SIZE: usize = 16;
+const DEFAULT_COMPARISON_LIMIT: usize = 25;
+const MAX_SIMILARITY_BOOKS: usize = 50;
+const DEFAULT_PAGE_SIZE: usize = 10_000;
+const MIN_PAGE_SIZE: usize = 500;
+const DEFAULT_SIMILAR_LIMIT: usize = 10;
+const MAX_SIMILAR_LIMIT: usize = 100;
+const DEFAULT_SUMMARY_SENTENCES: usize = 5;
+/// Sentences ranked and cached per book; smaller summaries are cut from them.
+const MAX_SUMMARY_SENTENCES: usize = 20;
+const DEFAULT_STYLE_BOOKS: i64 = 10;
+const DEFAULT_TOPICS: usize = 20;
+const MAX_TOPICS: usize = 100;
+const TOPIC_VOCABULARY_SIZE: usize = 5000;
+const TOPIC_ITERATIONS: usize = 200;
+/// Smaller topic weights of a book are not stored.
+const MIN_BOOK_TOPIC_WEIGHT: f64 = 0.001;
+const DEFAULT_TOPIC_WORDS: i64 = 10;
+const DEFAULT_AUTO_SHELVES: usize = 50;
+const MAX_AUTO_SHELVES: usize = 500;
+const AUTO_SHELF_ITERATIONS: usize = 50;
+const AUTO_SHELF_KEYWORDS: usize = 10;
+const DEFAULT_BOOK_TOPICS: i64 = 5;
+const MAX_ATTRIBUTION_AUTHORS: usize = 20;
+/// Largest JSON body accepted, enough for an uploaded book.
+pub const MAX_JSON_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct AnalyticsQuery {
+    n: Option<usize>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct ConcordanceQuery {
+    word: String,
+    window: Option<usize>,
+    limit: Option<usize>,
+    stem: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct DispersionQuery {
+    /// Comma-separated words or phrases.
+    words: String,
+    segments: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct ChaptersQuery {
+    top: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct DistinctiveQuery {
+    measure: Option<KeynessMeasure>,
+    limit: Option<usize>,
+    /// Compare against the books of one subject, bookshelf or author only.
+    subject: Option<i32>,
+    bookshelf: Option<i32>,
+    author: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct AggregateQuery {
+    top: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct AnalyzeAllQuery {
+    /// Re-analyze books that already have stored analytics.
+    force: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct TopicJobQuery {
+    topics: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct AutoShelfJobQuery {
+    shelves: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct TopicsQuery {
+    /// Top words listed per topic.
+    words: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct SentimentQuery {
+    segments: Option<usize>,
+    window: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct CastQuery {
+    min_mentions: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct CharacterNetworkQuery {
+    scope: Option<CooccurrenceScope>,
+    /// Tokens between two mentions, for the `window` scope.
+    window: Option<usize>,
+    min_mentions: Option<u32>,
+    format: Option<GraphFormat>,
+}
+
+#[derive(Deserialize)]
+pub struct KeyphrasesQuery {
+    method: Option<KeyphraseMethod>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct SummaryQuery {
+    sentences: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct SimilarQuery {
+    mode: Option<RecommendationMode>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct CompareQuery {
+    a: i64,
+    b: i64,
+    limit: Option<usize>,
+}
+
+/// Either an explicit list of books or a bookshelf whose most downloaded
+/// books are compared. Words are weighted by how many of these books use
+/// them, not by the whole catalog, so the same pair of books can score
+/// differently in a different set.
+#[derive(Deserialize)]
+pub struct SimilarityRequest {
+    book_ids: Option<Vec<i64>>,
+    shelf_id: Option<i32>,
+}
+
+/// A book from the catalog or an uploaded text, attributed to one of
+/// `authors`.
+#[derive(Deserialize)]
+pub struct AttributionRequest {
+    book_id: Option<i64>,
+    text: Option<String>,
+    authors: Vec<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct TextQuery {
+    /// One-based page number; the default when nothing else is selected.
+    page: Option<usize>,
+    page_size: Option<usize>,
+    chapter: Option<usize>,
+    /// Character range of the cleaned text.
+    start: Option<usize>,
+    end: Option<usize>,
+}
+
+/// Where `TEXT_CACHE_DIR`, if set, keeps the text of a book.
+fn cache_path(id: i64) -> Option<PathBuf> {
+    std::env::var("TEXT_CACHE_DIR")
+        .ok()
+        .map(|dir| PathBuf::from(dir).join(format!("{}.txt", id)))
+}
+
+async fn fetch_content_url(pool: &PgPool, id: i64) -> Result<String, HttpResponse> {
+    let res = sqlx::query!(
+        r#"
+        SELECT
+            content_url
+        FROM
+            books
+        WHERE
+            book_id = $1;
+        "#,
+        id
+    )
+    .fetch_one(pool)
+    .await;
+
+    let content_url = match res {
+        Ok(row) => row.content_url,
+        Err(sqlx::Error::RowNotFound) => {
+            return Err(HttpResponse::NotFound().body("Book not found"))
+        }
+        Err(e) => {
+            return Err(HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)))
+        }
+    };
+    content_url.ok_or_else(|| HttpResponse::NotFound().body("Book has no content"))
+}
+
+/// Starts downloading `url`, treating an error status from the server like
+/// a failed request, so that error pages are never analyzed as books.
+async fn download(url: &str) -> Result<reqwest::Response, HttpResponse> {
+    match reqwest::get(url)
+        .await
+        .and_then(|resp| resp.error_for_status())
+    {
+        Ok(resp) => Ok(resp),
+        Err(e) => Err(HttpResponse::BadGateway().body(format!("Error occurred{:?}", e))),
+    }
+}
+
+/// Looks up a book's content URL and downloads its text, without the
+/// Project Gutenberg header and license footer, so that every analysis
+/// and every offset refers to the same body. When `TEXT_CACHE_DIR` is
+/// set, downloads are kept there as they came.
+async fn fetch_book_content(pool: &PgPool, id: i64) -> Result<String, HttpResponse> {
+    let cache_path = cache_path(id);
+    if let Some(content) = cache_path
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+    {
+        return Ok(strip_boilerplate(&content).to_string());
+    }
+
+    let content_url = fetch_content_url(pool, id).await?;
+    let content = match download(&content_url).await?.text().await {
+        Ok(content) => content,
+        Err(e) => return Err(HttpResponse::BadGateway().body(format!("Error occurred{:?}", e))),
+    };
+
+    if let Some(path) = cache_path {
+        if !content.is_empty() {
+            if let Err(e) = std::fs::write(&path, &content) {
+                log::warn!("Could not cache book {} at {}: {}", id, path.display(), e);
+            }
+        }
+    }
+
+    Ok(strip_boilerplate(&content).to_string())
+}
+
+/// Analyzes a book's body while it downloads, without holding its whole
+/// text.
+async fn stream_book_analytics(pool: &PgPool, id: i64) -> Result<Analytics, HttpResponse> {
+    if let Some(content) = cache_path(id).and_then(|path| std::fs::read_to_string(path).ok()) {
+        return Ok(parallel::analyze(strip_boilerplate(&content)));
+    }
+
+    let content_url = fetch_content_url(pool, id).await?;
+    let mut resp = download(&content_url).await?;
+
+    let mut body = BoilerplateStripper::new();
+    let mut builder = AnalyticsBuilder::new();
+    loop {
+        match resp.chunk().await {
+            Ok(Some(chunk)) => builder.push(&body.push_bytes(&chunk)),
+            Ok(None) => break,
+            Err(e) => return Err(HttpResponse::BadGateway().body(format!("Error occurred{:?}", e))),
+        }
+    }
+    builder.push(&body.finish());
+
+    Ok(builder.build())
+}
+
+/// Stores a book's word counts, replacing those of an earlier analysis, so
+/// that corpus-level document frequencies cover every analyzed book.
+async fn record_word_counts(
+    pool: &PgPool,
+    id: i64,
+    word_map: &HashMap<String, u32>,
+) -> Result<(), sqlx::Error> {
+    let (words, counts): (Vec<String>, Vec<i32>) = word_map
+        .iter()
+        .map(|(word, &count)| (word.clone(), count as i32))
+        .unzip();
+    let token_count: i64 = counts.iter().map(|&count| count as i64).sum();
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO analyzed_books (book_id, token_count)
+        VALUES ($1, $2)
+        ON CONFLICT (book_id) DO UPDATE
+        SET token_count = EXCLUDED.token_count, analyzed_at = now();
+        "#,
+        id,
+        token_count
+    )
+    .execute(&mut tx)
+    .await?;
+    sqlx::query!("DELETE FROM book_word_counts WHERE book_id = $1;", id)
+        .execute(&mut tx)
+        .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO book_word_counts (book_id, word, count)
+        SELECT $1, * FROM UNNEST($2::TEXT[], $3::INT[]);
+        "#,
+        id,
+        &words,
+        &counts
+    )
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Document frequencies and counts of `words` over every analyzed book
+/// except `id`, optionally narrowed to a subject, bookshelf or author.
+async fn fetch_corpus_frequencies(
+    pool: &PgPool,
+    id: i64,
+    words: &[String],
+    query: &DistinctiveQuery,
+) -> Result<CorpusFrequencies, sqlx::Error> {
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) AS "document_count!",
+            COALESCE(SUM(token_count), 0)::BIGINT AS "total_tokens!"
+        FROM analyzed_books
+        WHERE book_id <> $1
+            AND ($2::INT IS NULL OR book_id IN (SELECT book_id FROM books_subjects WHERE subject_id = $2))
+            AND ($3::INT IS NULL OR book_id IN (SELECT book_id FROM books_bookshelves WHERE shelf_id = $3))
+            AND ($4::INT IS NULL OR book_id IN (SELECT book_id FROM books_authors WHERE author_id = $4));
+        "#,
+        id,
+        query.subject,
+        query.bookshelf,
+        query.author
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            word,
+            COUNT(*) AS "document_frequency!",
+            SUM(count)::BIGINT AS "total!"
+        FROM book_word_counts
+        WHERE book_id <> $1
+            AND word = ANY($2)
+            AND ($3::INT IS NULL OR book_id IN (SELECT book_id FROM books_subjects WHERE subject_id = $3))
+            AND ($4::INT IS NULL OR book_id IN (SELECT book_id FROM books_bookshelves WHERE shelf_id = $4))
+            AND ($5::INT IS NULL OR book_id IN (SELECT book_id FROM books_authors WHERE author_id = $5))
+        GROUP BY word;
+        "#,
+        id,
+        words,
+        query.subject,
+        query.bookshelf,
+        query.author
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut corpus = CorpusFrequencies {
+        document_count: totals.document_count as u32,
+        total_tokens: totals.total_tokens as u64,
+        ..Default::default()
+    };
+    for row in rows {
+        corpus
+            .document_frequencies
+            .insert(row.word.clone(), row.document_frequency as u32);
+        corpus.word_counts.insert(row.word, row.total as u64);
+    }
+
+    Ok(corpus)
+}
+
+/// Profile of a group of books from their stored analytics. Members not
+/// analyzed yet are handed to a background analysis job; until it is done
+/// the profile covers fewer than `total_books` books and the response is
+/// 202 Accepted, with the job in its `Location` header.
+async fn aggregate_analytics(
+    pool: &PgPool,
+    jobs: &web::Data<Jobs>,
+    book_ids: Vec<i64>,
+    top_words: u32,
+) -> HttpResponse {
+    if book_ids.is_empty() {
+        return HttpResponse::NotFound().body("No books found");
+    }
+
+    let res = sqlx::query!(
+        "SELECT book_id, analytics FROM book_analytics WHERE book_id = ANY($1);",
+        &book_ids
+    )
+    .fetch_all(pool)
+    .await;
+    let rows = match res {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))
+        }
+    };
+
+    let mut books = HashMap::new();
+    for row in rows {
+        if let Ok(analytics) = serde_json::from_value::<Analytics>(row.analytics) {
+            books.insert(row.book_id, analytics);
+        }
+    }
+    let missing: Vec<i64> = book_ids
+        .iter()
+        .copied()
+        .filter(|id| !books.contains_key(id))
+        .collect();
+
+    let total_books = book_ids.len();
+    let books: Vec<Analytics> = books.into_values().collect();
+    let profile = match web::block(move || CorpusProfile::new(&books, total_books, top_words)).await
+    {
+        Ok(profile) => profile,
+        Err(e) => {
+            return HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))
+        }
+    };
+
+    if missing.is_empty() {
+        return HttpResponse::Ok().json(profile);
+    }
+    // A running analysis job may already be working on the missing books.
+    let job_id = match jobs.start(JobKind::Analysis, missing.len()) {
+        Ok(job_id) => {
+            actix_web::rt::spawn(run_analysis_job(
+                pool.clone(),
+                jobs.clone(),
+                job_id,
+                missing,
+            ));
+            job_id
+        }
+        Err(running) => running.id,
+    };
+    HttpResponse::Accepted()
+        .insert_header((header::LOCATION, format!("/jobs/{}", job_id)))
+        .json(profile)
+}
+
+/// Saves a book's analytics and its word counts for later requests and
+/// corpus statistics. They must come from `AnalyticsBuilder` or
+/// `parallel::analyze`, which agree, so that what is stored does not depend
+/// on the request that analyzed the book.
+async fn store_analytics(pool: &PgPool, id: i64, analytics: &Analytics) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO book_analytics (book_id, analytics)
+        VALUES ($1, $2)
+        ON CONFLICT (book_id) DO UPDATE
+        SET analytics = EXCLUDED.analytics, analyzed_at = now();
+        "#,
+        id,
+        sqlx::types::Json(analytics) as _
+    )
+    .execute(pool)
+    .await?;
+    record_word_counts(pool, id, &analytics.word_map).await
+}
+
+/// A book's stored analytics, if it has been analyzed.
+async fn fetch_stored_analytics(pool: &PgPool, id: i64) -> Result<Option<Analytics>, HttpResponse> {
+    let stored = sqlx::query!(
+        "SELECT analytics FROM book_analytics WHERE book_id = $1;",
+        id
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match stored {
+        Ok(row) => Ok(row.and_then(|row| serde_json::from_value(row.analytics).ok())),
+        Err(e) => Err(HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))),
+    }
+}
+
+/// A book's stored analytics, or a fresh analysis that is then stored.
+async fn load_analytics(pool: &PgPool, id: i64) -> Result<Analytics, HttpResponse> {
+    if let Some(analytics) = fetch_stored_analytics(pool, id).await? {
+        return Ok(analytics);
+    }
+
+    let analytics = stream_book_analytics(pool, id).await?;
+    if let Err(e) = store_analytics(pool, id, &analytics).await {
+        log::warn!("Could not store analytics of book {}: {:?}", id, e);
+    }
+    Ok(analytics)
+}
+
+/// The analytics of every book of `book_ids`, in order. Books without stored
+/// analytics are downloaded and analyzed up to `BULK_BATCH_SIZE` at a time.
+async fn load_all_analytics(
+    pool: &PgPool,
+    book_ids: &[i64],
+) -> Result<Vec<Analytics>, HttpResponse> {
+    let mut analytics = Vec::with_capacity(book_ids.len());
+    for batch in book_ids.chunks(BULK_BATCH_SIZE) {
+        let loads: Vec<_> = batch
+            .iter()
+            .map(|&id| {
+                let pool = pool.clone();
+                actix_web::rt::spawn(async move { load_analytics(&pool, id).await })
+            })
+            .collect();
+
+        for load in loads {
+            match load.await {
+                Ok(loaded) => analytics.push(loaded?),
+                Err(e) => {
+                    return Err(
+                        HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))
+                    )
+                }
+            }
+        }
+    }
+    Ok(analytics)
+}
+
+/// A book's analytics for `/books/{id}`, with its `n`-grams if asked for.
+/// Those need the text, so then it is downloaded once for both.
+async fn book_analytics(
+    pool: &PgPool,
+    id: i64,
+    n: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Analytics, HttpResponse> {
+    let n = match n {
+        Some(n) => n,
+        None => return load_analytics(pool, id).await,
+    };
+
+    let content = fetch_book_content(pool, id).await?;
+    let mut analytics = match fetch_stored_analytics(pool, id).await? {
+        Some(analytics) => analytics,
+        None => {
+            let analytics = parallel::analyze(&content);
+            if let Err(e) = store_analytics(pool, id, &analytics).await {
+                log::warn!("Could not store analytics of book {}: {:?}", id, e);
+            }
+            analytics
+        }
+    };
+    let limit = limit.unwrap_or(DEFAULT_NGRAM_LIMIT);
+    analytics.ngrams = Some(Ngrams::new(&content, n, limit));
+    Ok(analytics)
+}
+
+/// Books whose content vectors are nearest to that of book `id`.
+async fn similar_by_content(
+    pool: &PgPool,
+    index: &RwLock<VectorIndex>,
+    id: i64,
+    limit: usize,
+) -> HttpResponse {
+    let neighbours = {
+        let index = index.read().unwrap();
+        if index.get(id).is_none() {
+            return HttpResponse::NotFound()
+                .body("No content vector for this book; run /jobs/vectors first");
+        }
+        index.nearest(id, limit)
+    };
+
+    let ids: Vec<i64> = neighbours.iter().map(|n| n.book_id).collect();
+    let res = sqlx::query!(
+        "SELECT book_id, title, downloads FROM books WHERE book_id = ANY($1);",
+        &ids
+    )
+    .fetch_all(pool)
+    .await;
+
+    match res {
+        Ok(rows) => {
+            let books: HashMap<i64, _> = rows.into_iter().map(|row| (row.book_id, row)).collect();
+            let similar: Vec<SimilarBook> = neighbours
+                .into_iter()
+                .filter_map(|neighbour| {
+                    let book = books.get(&neighbour.book_id)?;
+                    let similarity = neighbour.similarity as f64;
+                    Some(SimilarBook {
+                        book_id: book.book_id,
+                        title: book.title.clone().unwrap_or_default(),
+                        downloads: book.downloads.unwrap_or_default(),
+                        score: similarity,
+                        reasons: vec![MatchReason {
+                            kind: MatchKind::Vocabulary,
+                            name: "similar vocabulary".to_string(),
+                            weight: similarity,
+                        }],
+                    })
+                })
+                .collect();
+            HttpResponse::Ok().json(similar)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
+/// Document frequencies of every word over all books with recorded word
+/// counts.
+async fn fetch_document_frequencies(pool: &PgPool) -> Result<CorpusFrequencies, sqlx::Error> {
+    let totals = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) AS "document_count!",
+            COALESCE(SUM(token_count), 0)::BIGINT AS "total_tokens!"
+        FROM analyzed_books;
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT word, COUNT(*) AS "document_frequency!"
+        FROM book_word_counts
+        GROUP BY word;
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(CorpusFrequencies {
+        document_count: totals.document_count as u32,
+        total_tokens: totals.total_tokens as u64,
+        document_frequencies: rows
+            .into_iter()
+            .map(|row| (row.word, row.document_frequency as u32))
+            .collect(),
+        ..Default::default()
+    })
+}
+
+/// Every stored content vector, for the nearest-neighbour index.
+pub async fn load_vector_index(pool: &PgPool) -> Result<VectorIndex, sqlx::Error> {
+    let rows = sqlx::query!("SELECT book_id, vector FROM book_vectors;")
+        .fetch_all(pool)
+        .await?;
+
+    let mut index = VectorIndex::new();
+    for row in rows {
+        index.insert(row.book_id, &row.vector);
+    }
+    Ok(index)
+}
+
+/// Rebuilds the content vector of every book of `book_ids` from its stored
+/// analytics, then swaps the new vectors into the live index.
+async fn run_vector_job(
+    pool: PgPool,
+    jobs: web::Data<Jobs>,
+    index: web::Data<RwLock<VectorIndex>>,
+    job_id: u64,
+    book_ids: Vec<i64>,
+) {
+    let corpus = match fetch_document_frequencies(&pool).await {
+        Ok(corpus) => corpus,
+        Err(e) => {
+            jobs.update(job_id, |status| {
+                status.state = JobState::Failed;
+                status.error = Some(format!("{:?}", e));
+            });
+            return;
+        }
+    };
+
+    let mut built = VectorIndex::new();
+    for id in book_ids {
+        let stored = match load_analytics(&pool, id).await {
+            Ok(analytics) => {
+                let vector = content_vector(&analytics.word_map, &corpus);
+                let res = sqlx::query!(
+                    r#"
+                    INSERT INTO book_vectors (book_id, vector)
+                    VALUES ($1, $2)
+                    ON CONFLICT (book_id) DO UPDATE
+                    SET vector = EXCLUDED.vector, built_at = now();
+                    "#,
+                    id,
+                    &vector
+                )
+                .execute(&pool)
+                .await;
+                // Only vectors that made it to the table are served, so the
+                // index matches what a restart would load.
+                if res.is_ok() {
+                    built.insert(id, &vector);
+                }
+                res.is_ok()
+            }
+            Err(_) => false,
+        };
+
+        jobs.update(job_id, |status| {
+            if stored {
+                status.processed += 1;
+            } else {
+                status.failed += 1;
+            }
+        });
+    }
+
+    *index.write().unwrap() = built;
+    jobs.update(job_id, |status| status.state = JobState::Finished);
+}
+
+/// Fits a topic model to every book of `book_ids` from its stored analytics
+/// and replaces the stored topics with it.
+async fn run_topic_job(
+    pool: PgPool,
+    jobs: web::Data<Jobs>,
+    job_id: u64,
+    book_ids: Vec<i64>,
+    topics: usize,
+) {
+    let fail = |e: String| {
+        jobs.update(job_id, |status| {
+            status.state = JobState::Failed;
+            status.error = Some(e);
+        })
+    };
+
+    let corpus = match fetch_document_frequencies(&pool).await {
+        Ok(corpus) => corpus,
+        Err(e) => return fail(format!("{:?}", e)),
+    };
+    let vocabulary = Vocabulary::new(&corpus, TOPIC_VOCABULARY_SIZE);
+
+    let mut ids = Vec::new();
+    let mut documents = Vec::new();
+    for id in book_ids {
+        let loaded = match load_analytics(&pool, id).await {
+            Ok(analytics) => {
+                ids.push(id);
+                documents.push(vocabulary.document(&analytics.word_map));
+                true
+            }
+            Err(_) => false,
+        };
+        jobs.update(job_id, |status| {
+            if loaded {
+                status.processed += 1;
+            } else {
+                status.failed += 1;
+            }
+        });
+    }
+
+    // Fewer books than topics leave some topics empty; keep the stored
+    // model rather than replace it with that.
+    if documents.len() < topics {
+        return fail(format!(
+            "{} books could be loaded, but {} topics need at least as many",
+            documents.len(),
+            topics
+        ));
+    }
+
+    let model = match web::block(move || {
+        TopicModel::fit(&vocabulary, &documents, topics, TOPIC_ITERATIONS)
+    })
+    .await
+    {
+        Ok(model) => model,
+        Err(e) => return fail(format!("{:?}", e)),
+    };
+
+    match store_topics(&pool, &ids, &model).await {
+        Ok(()) => jobs.update(job_id, |status| status.state = JobState::Finished),
+        Err(e) => fail(format!("{:?}", e)),
+    }
+}
+
+async fn store_topics(
+    pool: &PgPool,
+    book_ids: &[i64],
+    model: &TopicModel,
+) -> Result<(), sqlx::Error> {
+    let mut topic_ids = Vec::new();
+    let mut words = Vec::new();
+    let mut word_weights = Vec::new();
+    for (topic, weights) in model.topic_words.iter().enumerate() {
+        for (word, &weight) in model.vocabulary.iter().zip(weights) {
+            if weight > 0.0 {
+                topic_ids.push(topic as i32);
+                words.push(word.clone());
+                word_weights.push(weight);
+            }
+        }
+    }
+
+    let mut books = Vec::new();
+    let mut book_topic_ids = Vec::new();
+    let mut book_weights = Vec::new();
+    for (&id, weights) in book_ids.iter().zip(&model.document_topics) {
+        for (topic, &weight) in weights.iter().enumerate() {
+            if weight >= MIN_BOOK_TOPIC_WEIGHT {
+                books.push(id);
+                book_topic_ids.push(topic as i32);
+                book_weights.push(weight);
+            }
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!("DELETE FROM topic_words;")
+        .execute(&mut tx)
+        .await?;
+    sqlx::query!("DELETE FROM book_topics;")
+        .execute(&mut tx)
+        .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO topic_words (topic_id, word, weight)
+        SELECT * FROM UNNEST($1::INT[], $2::TEXT[], $3::DOUBLE PRECISION[]);
+        "#,
+        &topic_ids,
+        &words,
+        &word_weights
+    )
+    .execute(&mut tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO book_topics (book_id, topic_id, weight)
+        SELECT * FROM UNNEST($1::BIGINT[], $2::INT[], $3::DOUBLE PRECISION[]);
+        "#,
+        &books,
+        &book_topic_ids,
+        &book_weights
+    )
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Clusters the books of the content vector index into `shelves` shelves,
+/// finds keywords for each and replaces the stored auto-shelves.
+async fn run_auto_shelf_job(
+    pool: PgPool,
+    jobs: web::Data<Jobs>,
+    job_id: u64,
+    index: VectorIndex,
+    shelves: usize,
+) {
+    let fail = |e: String| {
+        jobs.update(job_id, |status| {
+            status.state = JobState::Failed;
+            status.error = Some(e);
+        })
+    };
+
+    let book_ids: Vec<i64> = index.iter().map(|(id, _)| id).collect();
+    let clusters = match web::block(move || {
+        let vectors: Vec<&[f32]> = index.iter().map(|(_, vector)| vector).collect();
+        spherical_k_means(&vectors, shelves, AUTO_SHELF_ITERATIONS)
+    })
+    .await
+    {
+        Ok(clusters) => clusters,
+        Err(e) => return fail(format!("{:?}", e)),
+    };
+
+    let corpus = match fetch_document_frequencies(&pool).await {
+        Ok(corpus) => corpus,
+        Err(e) => return fail(format!("{:?}", e)),
+    };
+
+    let mut members: Vec<Vec<i64>> = vec![Vec::new(); shelves];
+    for (&id, &cluster) in book_ids.iter().zip(&clusters.assignments) {
+        members[cluster].push(id);
+    }
+
+    // Shelf ids are handed out to the non-empty clusters in order.
+    let mut shelf_ids = vec![0; shelves];
+    let mut auto_shelves = Vec::new();
+    for (cluster, ids) in members.iter().enumerate() {
+        if ids.is_empty() {
+            continue;
+        }
+        let rows = sqlx::query!(
+            r#"
+            SELECT word, COUNT(*) AS "document_frequency!"
+            FROM book_word_counts
+            WHERE book_id = ANY($1)
+            GROUP BY word;
+            "#,
+            ids
+        )
+        .fetch_all(&pool)
+        .await;
+
+        let member_frequencies: HashMap<String, u32> = match rows {
+            Ok(rows) => rows
+                .into_iter()
+                .map(|row| (row.word, row.document_frequency as u32))
+                .collect(),
+            Err(e) => return fail(format!("{:?}", e)),
+        };
+        let keywords = cluster_keywords(
+            &member_frequencies,
+            ids.len() as u32,
+            &corpus,
+            AUTO_SHELF_KEYWORDS,
+        );
+        shelf_ids[cluster] = auto_shelves.len() as i32 + 1;
+        auto_shelves.push(AutoShelf::new(
+            shelf_ids[cluster],
+            keywords,
+            ids.len() as i32,
+        ));
+        jobs.update(job_id, |status| status.processed += ids.len());
+    }
+
+    let book_shelves: Vec<i32> = clusters
+        .assignments
+        .iter()
+        .map(|&cluster| shelf_ids[cluster])
+        .collect();
+
+    match store_auto_shelves(
+        &pool,
+        &auto_shelves,
+        &book_ids,
+        &book_shelves,
+        &clusters.similarities,
+    )
+    .await
+    {
+        Ok(()) => jobs.update(job_id, |status| status.state = JobState::Finished),
+        Err(e) => fail(format!("{:?}", e)),
+    }
+}
+
+async fn store_auto_shelves(
+    pool: &PgPool,
+    shelves: &[AutoShelf],
+    book_ids: &[i64],
+    shelf_ids: &[i32],
+    similarities: &[f32],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("DELETE FROM auto_shelves;")
+        .execute(&mut tx)
+        .await?;
+    for shelf in shelves {
+        sqlx::query!(
+            r#"
+            INSERT INTO auto_shelves (shelf_id, shelf_name, keywords, count_of_books)
+            VALUES ($1, $2, $3, $4);
+            "#,
+            shelf.shelf_id,
+            shelf.shelf_name,
+            &shelf.keywords,
+            shelf.count_of_books
+        )
+        .execute(&mut tx)
+        .await?;
+    }
+    sqlx::query!(
+        r#"
+        INSERT INTO books_auto_shelves (book_id, shelf_id, similarity)
+        SELECT * FROM UNNEST($1::BIGINT[], $2::INT[], $3::REAL[]);
+        "#,
+        book_ids,
+        shelf_ids,
+        similarities
+    )
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await
+}
+
+/// The `limit` heaviest words of each topic, or of only `topic_ids` when
+/// given.
+async fn fetch_topic_words(
+    pool: &PgPool,
+    topic_ids: Option<Vec<i32>>,
+    limit: i64,
+) -> Result<HashMap<i32, Vec<TopicWord>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT topic_id AS "topic_id!", word AS "word!", weight AS "weight!"
+        FROM (
+            SELECT
+                topic_id,
+                word,
+                weight,
+                ROW_NUMBER() OVER (PARTITION BY topic_id ORDER BY weight DESC, word) AS rank
+            FROM topic_words
+            WHERE $1::INT[] IS NULL OR topic_id = ANY($1)
+        ) AS ranked
+        WHERE rank <= $2
+        ORDER BY topic_id, rank;
+        "#,
+        topic_ids.as_deref(),
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut topics: HashMap<i32, Vec<TopicWord>> = HashMap::new();
+    for row in rows {
+        topics.entry(row.topic_id).or_default().push(TopicWord {
+            word: row.word,
+            weight: row.weight,
+        });
+    }
+    Ok(topics)
+}
+
+/// Function word profile of an author's most downloaded analyzed books,
+/// leaving out `exclude` so that a book is never compared against itself.
+/// It is built from the recorded word counts, so nothing is downloaded.
+async fn author_style(
+    pool: &PgPool,
+    author_id: i32,
+    exclude: Option<i64>,
+) -> Result<AuthorStyle, HttpResponse> {
+    let author = sqlx::query!(
+        "SELECT author_name FROM authors WHERE author_id = $1;",
+        author_id
+    )
+    .fetch_optional(pool)
+    .await;
+    let author_name = match author {
+        Ok(Some(row)) => row.author_name.unwrap_or_default(),
+        Ok(None) => return Err(HttpResponse::NotFound().body("Author not found")),
+        Err(e) => {
+            return Err(HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)))
+        }
+    };
+
+    let books = sqlx::query!(
+        r#"
+        SELECT DISTINCT books.book_id, books.downloads, analyzed_books.token_count
+        FROM books
+        INNER JOIN books_authors ON books.book_id = books_authors.book_id
+        INNER JOIN analyzed_books ON books.book_id = analyzed_books.book_id
+        WHERE books_authors.author_id = $1
+            AND ($2::BIGINT IS NULL OR books.book_id <> $2)
+        ORDER BY books.downloads DESC NULLS LAST
+        LIMIT $3;
+        "#,
+        author_id,
+        exclude,
+        DEFAULT_STYLE_BOOKS
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)))?;
+
+    let book_ids: Vec<i64> = books.iter().map(|book| book.book_id).collect();
+    let token_count: i64 = books.iter().map(|book| book.token_count).sum();
+    let function_words: Vec<String> = stop_words().into_iter().map(String::from).collect();
+    let counts = sqlx::query!(
+        r#"
+        SELECT word, SUM(count)::BIGINT AS "count!"
+        FROM book_word_counts
+        WHERE book_id = ANY($1) AND word = ANY($2)
+        GROUP BY word;
+        "#,
+        &book_ids,
+        &function_words
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)))?;
+
+    let counts: HashMap<String, u64> = counts
+        .into_iter()
+        .map(|row| (row.word, row.count as u64))
+        .collect();
+    Ok(AuthorStyle {
+        author_id,
+        author_name,
+        book_count: book_ids.len(),
+        profile: StyleProfile::from_counts(token_count as u64, &counts),
+    })
+}
+
+/// Downloads, analyzes and stores every book of `book_ids`, a batch at a
+/// time: downloads run concurrently and the analysis runs on all cores.
+async fn run_analysis_job(pool: PgPool, jobs: web::Data<Jobs>, job_id: u64, book_ids: Vec<i64>) {
+    for batch in book_ids.chunks(BULK_BATCH_SIZE) {
+        let downloads: Vec<_> = batch
+            .iter()
+            .map(|&id| {
+                let pool = pool.clone();
+                actix_web::rt::spawn(async move { (id, fetch_book_content(&pool, id).await.ok()) })
+            })
+            .collect();
+
+        let mut ids = Vec::new();
+        let mut contents = Vec::new();
+        for download in downloads {
+            match download.await {
+                Ok((id, Some(content))) => {
+                    ids.push(id);
+                    contents.push(content);
+                }
+                _ => jobs.update(job_id, |status| status.failed += 1),
+            }
+        }
+
+        let analytics = match web::block(move || parallel::analyze_books(&contents)).await {
+            Ok(analytics) => analytics,
+            Err(e) => {
+                jobs.update(job_id, |status| {
+                    status.state = JobState::Failed;
+                    status.error = Some(format!("{:?}", e));
+                });
+                return;
+            }
+        };
+
+        for (id, analytics) in ids.into_iter().zip(analytics) {
+            let stored = store_analytics(&pool, id, &analytics).await.is_ok();
+
+            jobs.update(job_id, |status| {
+                if stored {
+                    status.processed += 1;
+                } else {
+                    status.failed += 1;
+                }
+            });
+        }
+    }
+
+    jobs.update(job_id, |status| status.state = JobState::Finished);
+}
+
+/// Serves `text` as plain text with a strong ETag, answering conditional
+/// (`If-None-Match`) and single byte-range (`Range`, `If-Range`) requests.
+fn text_response(req: &HttpRequest, text: String, mut response: HttpResponse) -> HttpResponse {
+    let etag = EntityTag::new_strong(format!("{:016x}", fnv1a(text.as_bytes())));
+
+    let not_modified = match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        Err(_) => false,
+    };
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header(header::ETag(etag))
+            .finish();
+    }
+
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    headers.insert(
+        header::ACCEPT_RANGES,
+        header::HeaderValue::from_static("bytes"),
+    );
+    if let Ok(value) = header::HeaderValue::from_str(&etag.to_string()) {
+        headers.insert(header::ETAG, value);
+    }
+
+    let range_applies = match IfRange::parse(req) {
+        Ok(IfRange::EntityTag(tag)) => tag.strong_eq(&etag),
+        Ok(IfRange::Date(_)) => false,
+        Err(_) => true,
+    };
+    let ranges = match Range::parse(req) {
+        Ok(Range::Bytes(ranges)) if range_applies && ranges.len() == 1 => ranges,
+        _ => return response.set_body(text).map_into_boxed_body(),
+    };
+
+    let length = text.len() as u64;
+    match ranges[0].to_satisfiable_range(length) {
+        Some((from, to)) => {
+            let body = text.as_bytes()[from as usize..=to as usize].to_vec();
+            let mut partial = response.set_body(body).map_into_boxed_body();
+            *partial.status_mut() = actix_web::http::StatusCode::PARTIAL_CONTENT;
+            let content_range = ContentRangeSpec::Bytes {
+                range: Some((from, to)),
+                instance_length: Some(length),
+            };
+            if let Ok(value) = header::HeaderValue::from_str(&content_range.to_string()) {
+                partial.headers_mut().insert(header::CONTENT_RANGE, value);
+            }
+            partial
+        }
+        None => HttpResponse::RangeNotSatisfiable()
+            .insert_header(header::ContentRange(ContentRangeSpec::Bytes {
+                range: None,
+                instance_length: Some(length),
+            }))
+            .finish(),
+    }
+}
+
+#[get("/")]
+pub async fn get_top_ten_books(pool: web::Data<PgPool>) -> impl Responder {
+    let res = sqlx::query!(
+        r#"
+        SELECT
+            books.book_id,
+            books.title,
+            books.content_url,
+            books.downloads,
+            books.category,
+            books.cover_image_url_medium,
+            books.cover_image_url_small,
+            languages.language_name,
+            COALESCE(
+                (SELECT json_agg(json_build_object('author_id', authors.author_id, 'author_name', authors.author_name, 'year_of_birth', authors.year_of_birth, 'year_of_death', authors.year_of_death)) 
+                FROM books_authors 
+                INNER JOIN authors ON books_authors.author_id = authors.author_id 
+                WHERE books.book_id = books_authors.book_id), '[]') AS authors,
+            COALESCE(
+                (SELECT json_agg(json_build_object('subject_id', s.subject_id, 'subject_name', s.subject_name)) 
+                FROM 
+                    (SELECT DISTINCT subjects.subject_id, subjects.subject_name 
+                    FROM books_subjects 
+                    INNER JOIN subjects ON books_subjects.subject_id = subjects.subject_id 
+                    WHERE books.book_id = books_subjects.book_id) AS s), '[]') AS subjects,
+            COALESCE(
+                (SELECT json_agg(json_build_object('shelf_id', b.shelf_id, 'shelf_name', b.shelf_name)) 
+                FROM 
+                    (SELECT DISTINCT bookshelves.shelf_id, bookshelves.shelf_name 
+                    FROM books_bookshelves 
+                    INNER JOIN bookshelves ON books_bookshelves.shelf_id = bookshelves.shelf_id 
+                    WHERE books.book_id = books_bookshelves.book_id) AS b), '[]') AS bookshelves
+        FROM 
+            books
+        INNER JOIN
+            languages ON books.language_id = languages.language_id
+        GROUP BY
+            books.book_id,
+            languages.language_name
+        ORDER BY
+            books.downloads DESC
+        LIMIT 10;
+        "#
+    ).fetch_all(&**pool).await;
+
+    match res {
+        Ok(rows) => {
+            let mut books: Vec<Book> = Vec::new();
+
+            for row in rows {
+                let subjects_json = row
+                    .subjects
+                    .as_ref()
+                    .map_or(String::from("[]"), |jv| jv.to_string());
+                let subjects: Vec<Subject> = serde_json::from_str(&subjects_json).unwrap();
+
+                let bookshelves_json = row
+                    .bookshelves
+                    .as_ref()
+                    .map_or(String::from("[]"), |jv| jv.to_string());
+                let bookshelves: Vec<Bookshelf> = serde_json::from_str(&bookshelves_json).unwrap();
+
+                let authors_json = row
+                    .authors
+                    .as_ref()
+                    .map_or(String::from("[]"), |jv| jv.to_string());
+                let authors: Vec<Author> = serde_json::from_str(&authors_json).unwrap();
+
+                let book = Book {
+                    book_id: row.book_id,
+                    authors,
+                    title: row.title.unwrap_or_default(),
+                    language: row.language_name.unwrap_or_default(),
+                    downloads: row.downloads.unwrap_or_default(),
+                    bookshelves: Some(bookshelves),
+                    subjects: Some(subjects),
+                    category: row.category.unwrap_or_default(),
+                    content_url: row.content_url,
+                    cover_image_url_small: row.cover_image_url_small,
+                    cover_image_url_medium: row.cover_image_url_medium,
+                    analytics: None,
+                };
+                books.push(book);
+            }
+
+            HttpResponse::Ok().json(books)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
+#[get("/books/{id}")]
+pub async fn get_book(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<AnalyticsQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    if let Some(n) = query.n {
+        if !(2..=MAX_NGRAM_SIZE).contains(&n) {
+            return HttpResponse::BadRequest()
+                .body(format!("n must be between 2 and {}", MAX_NGRAM_SIZE));
+        }
+    }
     let res = sqlx::query!(
         r#"
         SELECT
@@ -39,153 +1398,854 @@ pub async fn get_top_ten_books(pool: web::Data<PgPool>) -> impl Responder {
             books
         INNER JOIN
             languages ON books.language_id = languages.language_id
+        WHERE book_id = $1
         GROUP BY
             books.book_id,
-            languages.language_name
-        ORDER BY
-            books.downloads DESC
-        LIMIT 10;
-        "#
-    ).fetch_all(&**pool).await;
+            languages.language_name;
+        "#, id
+    ).fetch_one(&**pool).await;
 
     match res {
-        Ok(rows) => {
-            let mut books: Vec<Book> = Vec::new();
+        Ok(res) => {
+            let analytics = match book_analytics(&pool, id, query.n, query.limit).await {
+                Ok(analytics) => analytics,
+                Err(response) => return response,
+            };
 
-            for row in rows {
-                let subjects_json = row
-                    .subjects
-                    .as_ref()
-                    .map_or(String::from("[]"), |jv| jv.to_string());
-                let subjects: Vec<Subject> = serde_json::from_str(&subjects_json).unwrap();
+            let subjects_json = res
+                .subjects
+                .as_ref()
+                .map_or(String::from("[]"), |jv| jv.to_string());
+            let subjects: Vec<Subject> = serde_json::from_str(&subjects_json).unwrap();
 
-                let bookshelves_json = row
-                    .bookshelves
-                    .as_ref()
-                    .map_or(String::from("[]"), |jv| jv.to_string());
-                let bookshelves: Vec<Bookshelf> = serde_json::from_str(&bookshelves_json).unwrap();
+            let bookshelves_json = res
+                .bookshelves
+                .as_ref()
+                .map_or(String::from("[]"), |jv| jv.to_string());
+            let bookshelves: Vec<Bookshelf> = serde_json::from_str(&bookshelves_json).unwrap();
+
+            let authors_json = res
+                .authors
+                .as_ref()
+                .map_or(String::from("[]"), |jv| jv.to_string());
+            let authors: Vec<Author> = serde_json::from_str(&authors_json).unwrap();
+
+            let book = Book {
+                book_id: res.book_id,
+                authors,
+                title: res.title.unwrap_or_default(),
+                language: res.language_name.unwrap_or_default(),
+                downloads: res.downloads.unwrap_or_default(),
+                bookshelves: Some(bookshelves),
+                subjects: Some(subjects),
+                category: res.category.unwrap_or_default(),
+                content_url: res.content_url,
+                cover_image_url_small: res.cover_image_url_small,
+                cover_image_url_medium: res.cover_image_url_medium,
+                analytics: Some(analytics),
+            };
+
+            HttpResponse::Ok().json(book)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
+#[get("/books/{id}/concordance")]
+pub async fn get_concordance(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<ConcordanceQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+
+    let window = query
+        .window
+        .unwrap_or(DEFAULT_CONCORDANCE_WINDOW)
+        .min(MAX_CONCORDANCE_WINDOW);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CONCORDANCE_LIMIT)
+        .min(MAX_CONCORDANCE_LIMIT);
+    let concordance = Concordance::new(
+        &content,
+        &query.word,
+        window,
+        limit,
+        query.stem.unwrap_or(false),
+    );
+
+    HttpResponse::Ok().json(concordance)
+}
+
+#[get("/books/{id}/dispersion")]
+pub async fn get_dispersion(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<DispersionQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let words: Vec<String> = query
+        .words
+        .split(',')
+        .map(|word| word.trim().to_string())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        return HttpResponse::BadRequest().body("words must name at least one word");
+    }
+
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+
+    let segments = query
+        .segments
+        .unwrap_or(DEFAULT_DISPERSION_SEGMENTS)
+        .min(MAX_DISPERSION_SEGMENTS);
+
+    HttpResponse::Ok().json(Dispersion::new(&content, &words, segments))
+}
+
+#[get("/books/{id}/chapters")]
+pub async fn get_chapters(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<ChaptersQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+
+    let top_words = query.top.unwrap_or(DEFAULT_CHAPTER_TOP_WORDS);
+    let chapters = Chapter::table_of_contents(&content, top_words);
+
+    HttpResponse::Ok().json(chapters)
+}
+
+#[get("/books/{id}/sentiment")]
+pub async fn get_sentiment(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<SentimentQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+
+    let segments = query
+        .segments
+        .unwrap_or(DEFAULT_SENTIMENT_SEGMENTS)
+        .min(MAX_SENTIMENT_SEGMENTS);
+    let window = query.window.unwrap_or(DEFAULT_SENTIMENT_WINDOW);
+    HttpResponse::Ok().json(SentimentArc::new(&content, segments, window))
+}
+
+#[get("/books/{id}/characters")]
+pub async fn get_characters(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<CastQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+
+    let min_mentions = query.min_mentions.unwrap_or(DEFAULT_MIN_MENTIONS);
+    HttpResponse::Ok().json(cast(&content, min_mentions))
+}
+
+#[get("/books/{id}/characters/network")]
+pub async fn get_character_network(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<CharacterNetworkQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+
+    let scope = query.scope.unwrap_or_default();
+    let window = query
+        .window
+        .unwrap_or(DEFAULT_COOCCURRENCE_WINDOW)
+        .min(MAX_COOCCURRENCE_WINDOW);
+    let min_mentions = query.min_mentions.unwrap_or(DEFAULT_MIN_MENTIONS);
+    let network = CharacterNetwork::new(&content, scope, window, min_mentions);
+
+    match query.format.unwrap_or_default() {
+        GraphFormat::Json => HttpResponse::Ok().json(network),
+        GraphFormat::Graphml => HttpResponse::Ok()
+            .content_type("application/graphml+xml; charset=utf-8")
+            .body(network.to_graphml()),
+    }
+}
+
+#[get("/books/{id}/keyphrases")]
+pub async fn get_keyphrases(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<KeyphrasesQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+
+    let method = query.method.unwrap_or_default();
+    let limit = query.limit.unwrap_or(DEFAULT_KEYPHRASE_LIMIT);
+    HttpResponse::Ok().json(Keyphrases::new(&content, method, limit))
+}
+
+#[get("/books/{id}/chapters/keyphrases")]
+pub async fn get_chapter_keyphrases(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<KeyphrasesQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+
+    let method = query.method.unwrap_or_default();
+    let limit = query.limit.unwrap_or(DEFAULT_CHAPTER_KEYPHRASES);
+    HttpResponse::Ok().json(Keyphrases::by_chapter(&content, method, limit))
+}
+
+#[get("/books/{id}/distinctive")]
+pub async fn get_distinctive_words(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<DistinctiveQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let stored = sqlx::query!(
+        "SELECT word, count FROM book_word_counts WHERE book_id = $1;",
+        id
+    )
+    .fetch_all(&**pool)
+    .await;
+    let word_map: HashMap<String, u32> = match stored {
+        Ok(rows) if !rows.is_empty() => rows
+            .into_iter()
+            .map(|row| (row.word, row.count as u32))
+            .collect(),
+        // Analyzing the book records its word counts for next time.
+        Ok(_) => match load_analytics(&pool, id).await {
+            Ok(analytics) => analytics.word_map,
+            Err(response) => return response,
+        },
+        Err(e) => {
+            return HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))
+        }
+    };
+
+    let words: Vec<String> = word_map.keys().cloned().collect();
+    let corpus = match fetch_corpus_frequencies(&pool, id, &words, &query).await {
+        Ok(corpus) => corpus,
+        Err(e) => {
+            return HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))
+        }
+    };
+
+    HttpResponse::Ok().json(DistinctiveWords::new(
+        &word_map,
+        &corpus,
+        query.measure.unwrap_or_default(),
+        query.limit.unwrap_or(DEFAULT_DISTINCTIVE_LIMIT),
+    ))
+}
+
+#[get("/topics")]
+pub async fn get_topics(pool: web::Data<PgPool>, query: web::Query<TopicsQuery>) -> impl Responder {
+    let limit = query.words.unwrap_or(DEFAULT_TOPIC_WORDS);
+    match fetch_topic_words(&pool, None, limit).await {
+        Ok(topics) => {
+            let mut topics: Vec<TopicSummary> = topics
+                .into_iter()
+                .map(|(topic_id, words)| TopicSummary { topic_id, words })
+                .collect();
+            topics.sort_by_key(|topic| topic.topic_id);
+            HttpResponse::Ok().json(topics)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
+#[get("/books/{id}/topics")]
+pub async fn get_book_topics(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<TopicsQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let res = sqlx::query!(
+        r#"
+        SELECT topic_id, weight
+        FROM book_topics
+        WHERE book_id = $1
+        ORDER BY weight DESC, topic_id
+        LIMIT $2;
+        "#,
+        id,
+        query.limit.unwrap_or(DEFAULT_BOOK_TOPICS)
+    )
+    .fetch_all(&**pool)
+    .await;
+
+    let rows = match res {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))
+        }
+    };
+    if rows.is_empty() {
+        return HttpResponse::NotFound().body("No topics for this book; run /jobs/topics first");
+    }
+
+    let topic_ids = rows.iter().map(|row| row.topic_id).collect();
+    let limit = query.words.unwrap_or(DEFAULT_TOPIC_WORDS);
+    match fetch_topic_words(&pool, Some(topic_ids), limit).await {
+        Ok(mut words) => {
+            let topics: Vec<BookTopic> = rows
+                .into_iter()
+                .map(|row| BookTopic {
+                    topic_id: row.topic_id,
+                    weight: row.weight,
+                    words: words.remove(&row.topic_id).unwrap_or_default(),
+                })
+                .collect();
+            HttpResponse::Ok().json(topics)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
+#[get("/books/{id}/summary")]
+pub async fn get_summary(
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<SummaryQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let sentences = query
+        .sentences
+        .unwrap_or(DEFAULT_SUMMARY_SENTENCES)
+        .clamp(1, MAX_SUMMARY_SENTENCES);
+
+    let stored = match fetch_stored_analytics(&pool, id).await {
+        Ok(stored) => stored,
+        Err(response) => return response,
+    };
+    if let Some(summary) = stored
+        .as_ref()
+        .and_then(|analytics| analytics.summary.as_ref())
+    {
+        return HttpResponse::Ok().json(summary.top(sentences));
+    }
+
+    // One download serves both the summary and, for a book not analyzed
+    // yet, its analytics.
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+    let (mut analytics, summary) = match web::block(move || {
+        let summary = Summary::new(&content, MAX_SUMMARY_SENTENCES);
+        (
+            stored.unwrap_or_else(|| parallel::analyze(&content)),
+            summary,
+        )
+    })
+    .await
+    {
+        Ok(built) => built,
+        Err(e) => {
+            return HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))
+        }
+    };
+
+    let response = HttpResponse::Ok().json(summary.top(sentences));
+    // Caching the summary must not fail the request.
+    analytics.summary = Some(summary);
+    if let Err(e) = store_analytics(&pool, id, &analytics).await {
+        log::warn!("Could not store the summary of book {}: {:?}", id, e);
+    }
+    response
+}
+
+#[get("/books/{id}/similar")]
+pub async fn get_similar_books(
+    pool: web::Data<PgPool>,
+    index: web::Data<RwLock<VectorIndex>>,
+    path: web::Path<i64>,
+    query: web::Query<SimilarQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SIMILAR_LIMIT)
+        .clamp(1, MAX_SIMILAR_LIMIT);
+    if query.mode.unwrap_or_default() == RecommendationMode::Content {
+        return similar_by_content(&pool, &index, id, limit).await;
+    }
+    // Scores every book sharing some metadata the way SimilarBook::rank
+    // does, so that only the best `limit` need to be loaded and ranked.
+    let res = sqlx::query!(
+        r#"
+        WITH shared AS (
+            SELECT book_id, SUM(weight) AS score
+            FROM (
+                SELECT DISTINCT other.book_id, other.author_id AS id, $2::float8 AS weight
+                FROM books_authors own
+                INNER JOIN books_authors other ON own.author_id = other.author_id
+                WHERE own.book_id = $1 AND other.book_id <> $1
+                UNION ALL
+                SELECT DISTINCT other.book_id, other.shelf_id, $3::float8
+                FROM books_bookshelves own
+                INNER JOIN books_bookshelves other ON own.shelf_id = other.shelf_id
+                WHERE own.book_id = $1 AND other.book_id <> $1
+                UNION ALL
+                SELECT DISTINCT other.book_id, other.subject_id, $4::float8
+                FROM books_subjects own
+                INNER JOIN books_subjects other ON own.subject_id = other.subject_id
+                WHERE own.book_id = $1 AND other.book_id <> $1
+            ) AS matches
+            GROUP BY book_id
+        ),
+        candidates AS (
+            SELECT shared.book_id
+            FROM shared
+            INNER JOIN books ON shared.book_id = books.book_id
+            ORDER BY
+                shared.score + CASE
+                    WHEN books.language_id = (SELECT language_id FROM books WHERE book_id = $1)
+                    THEN $5::float8 ELSE 0 END DESC,
+                COALESCE(books.downloads, 0) DESC,
+                books.book_id
+            LIMIT $6
+        )
+        SELECT
+            books.book_id,
+            books.title,
+            books.downloads,
+            languages.language_name AS "language_name?",
+            COALESCE(
+                (SELECT json_agg(json_build_object('author_id', authors.author_id, 'author_name', authors.author_name, 'year_of_birth', authors.year_of_birth, 'year_of_death', authors.year_of_death))
+                FROM books_authors
+                INNER JOIN authors ON books_authors.author_id = authors.author_id
+                WHERE books.book_id = books_authors.book_id), '[]') AS authors,
+            COALESCE(
+                (SELECT json_agg(json_build_object('subject_id', s.subject_id, 'subject_name', s.subject_name))
+                FROM
+                    (SELECT DISTINCT subjects.subject_id, subjects.subject_name
+                    FROM books_subjects
+                    INNER JOIN subjects ON books_subjects.subject_id = subjects.subject_id
+                    WHERE books.book_id = books_subjects.book_id) AS s), '[]') AS subjects,
+            COALESCE(
+                (SELECT json_agg(json_build_object('shelf_id', b.shelf_id, 'shelf_name', b.shelf_name))
+                FROM
+                    (SELECT DISTINCT bookshelves.shelf_id, bookshelves.shelf_name
+                    FROM books_bookshelves
+                    INNER JOIN bookshelves ON books_bookshelves.shelf_id = bookshelves.shelf_id
+                    WHERE books.book_id = books_bookshelves.book_id) AS b), '[]') AS bookshelves
+        FROM
+            books
+        LEFT JOIN
+            languages ON books.language_id = languages.language_id
+        WHERE books.book_id = $1 OR books.book_id IN (SELECT book_id FROM candidates)
+        ORDER BY books.book_id = $1 DESC;
+        "#,
+        id,
+        AUTHOR_WEIGHT,
+        BOOKSHELF_WEIGHT,
+        SUBJECT_WEIGHT,
+        LANGUAGE_WEIGHT,
+        limit as i64
+    )
+    .fetch_all(&**pool)
+    .await;
+
+    let rows = match res {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e))
+        }
+    };
+
+    let books: Vec<Book> = rows
+        .into_iter()
+        .map(|row| Book {
+            book_id: row.book_id,
+            authors: serde_json::from_value(row.authors.unwrap_or_default()).unwrap_or_default(),
+            title: row.title.unwrap_or_default(),
+            language: row.language_name.unwrap_or_default(),
+            downloads: row.downloads.unwrap_or_default(),
+            bookshelves: serde_json::from_value(row.bookshelves.unwrap_or_default()).ok(),
+            subjects: serde_json::from_value(row.subjects.unwrap_or_default()).ok(),
+            ..Default::default()
+        })
+        .collect();
+
+    match books.first() {
+        Some(book) if book.book_id == id => {
+            HttpResponse::Ok().json(SimilarBook::rank(book, &books[1..], limit))
+        }
+        _ => HttpResponse::NotFound().body("Book not found"),
+    }
+}
+
+#[get("/compare")]
+pub async fn compare_books(
+    pool: web::Data<PgPool>,
+    query: web::Query<CompareQuery>,
+) -> impl Responder {
+    let a = match load_analytics(&pool, query.a).await {
+        Ok(analytics) => analytics,
+        Err(response) => return response,
+    };
+    let b = match load_analytics(&pool, query.b).await {
+        Ok(analytics) => analytics,
+        Err(response) => return response,
+    };
+
+    let limit = query.limit.unwrap_or(DEFAULT_COMPARISON_LIMIT);
+    HttpResponse::Ok().json(Comparison::new(&a.word_map, &b.word_map, limit))
+}
+
+#[post("/similarity")]
+pub async fn get_similarity_matrix(
+    pool: web::Data<PgPool>,
+    body: web::Json<SimilarityRequest>,
+) -> impl Responder {
+    let book_ids = match (&body.book_ids, body.shelf_id) {
+        (Some(book_ids), _) => book_ids.clone(),
+        (None, Some(shelf_id)) => {
+            let res = sqlx::query!(
+                r#"
+                SELECT DISTINCT books.book_id, books.downloads
+                FROM books
+                INNER JOIN books_bookshelves ON books.book_id = books_bookshelves.book_id
+                WHERE books_bookshelves.shelf_id = $1
+                ORDER BY books.downloads DESC NULLS LAST
+                LIMIT $2;
+                "#,
+                shelf_id,
+                MAX_SIMILARITY_BOOKS as i64
+            )
+            .fetch_all(&**pool)
+            .await;
+
+            match res {
+                Ok(rows) => rows.into_iter().map(|row| row.book_id).collect(),
+                Err(e) => {
+                    return HttpResponse::InternalServerError()
+                        .body(format!("Error occurred{:?}", e))
+                }
+            }
+        }
+        (None, None) => {
+            return HttpResponse::BadRequest().body("Either book_ids or shelf_id is required")
+        }
+    };
+
+    if book_ids.len() < 2 || book_ids.len() > MAX_SIMILARITY_BOOKS {
+        return HttpResponse::BadRequest().body(format!(
+            "Between 2 and {} books can be compared",
+            MAX_SIMILARITY_BOOKS
+        ));
+    }
+
+    let analytics = match load_all_analytics(&pool, &book_ids).await {
+        Ok(analytics) => analytics,
+        Err(response) => return response,
+    };
+
+    let word_maps: Vec<_> = analytics.iter().map(|book| &book.word_map).collect();
+    HttpResponse::Ok().json(SimilarityMatrix::new(book_ids, &word_maps))
+}
+
+#[post("/attribution")]
+pub async fn attribute_authorship(
+    pool: web::Data<PgPool>,
+    body: web::Json<AttributionRequest>,
+) -> impl Responder {
+    let body = body.into_inner();
+    if body.authors.len() < MIN_CANDIDATES || body.authors.len() > MAX_ATTRIBUTION_AUTHORS {
+        return HttpResponse::BadRequest().body(format!(
+            "Between {} and {} candidate authors are needed",
+            MIN_CANDIDATES, MAX_ATTRIBUTION_AUTHORS
+        ));
+    }
+
+    let text = match (body.text, body.book_id) {
+        (Some(text), _) => StyleProfile::new(&[&count_words(&text)]),
+        (None, Some(id)) => match load_analytics(&pool, id).await {
+            Ok(analytics) => StyleProfile::new(&[&analytics.word_map]),
+            Err(response) => return response,
+        },
+        (None, None) => {
+            return HttpResponse::BadRequest().body("Either book_id or text is required")
+        }
+    };
+    if text.token_count == 0 {
+        return HttpResponse::BadRequest().body("The text contains no words");
+    }
+
+    let mut authors = Vec::new();
+    for &author_id in &body.authors {
+        match author_style(&pool, author_id, body.book_id).await {
+            Ok(style) if style.book_count > 0 => authors.push(style),
+            Ok(_) => {}
+            Err(response) => return response,
+        }
+    }
+    if authors.len() < MIN_CANDIDATES {
+        return HttpResponse::BadRequest().body(format!(
+            "At least {} candidate authors need analyzed books; run /jobs/analyze first",
+            MIN_CANDIDATES
+        ));
+    }
+
+    HttpResponse::Ok().json(Attribution::new(&text, &authors))
+}
+
+#[post("/jobs/analyze")]
+pub async fn start_analysis_job(
+    pool: web::Data<PgPool>,
+    jobs: web::Data<Jobs>,
+    query: web::Query<AnalyzeAllQuery>,
+) -> impl Responder {
+    let res = sqlx::query!(
+        r#"
+        SELECT books.book_id
+        FROM books
+        LEFT JOIN book_analytics ON books.book_id = book_analytics.book_id
+        WHERE books.content_url IS NOT NULL
+            AND ($1 OR book_analytics.book_id IS NULL)
+        ORDER BY books.book_id;
+        "#,
+        query.force.unwrap_or(false)
+    )
+    .fetch_all(&**pool)
+    .await;
 
-                let authors_json = row
-                    .authors
-                    .as_ref()
-                    .map_or(String::from("[]"), |jv| jv.to_string());
-                let authors: Vec<Author> = serde_json::from_str(&authors_json).unwrap();
+    match res {
+        Ok(rows) => {
+            let book_ids: Vec<i64> = rows.into_iter().map(|row| row.book_id).collect();
+            let job_id = match jobs.start(JobKind::Analysis, book_ids.len()) {
+                Ok(job_id) => job_id,
+                Err(running) => return HttpResponse::Conflict().json(running),
+            };
+            actix_web::rt::spawn(run_analysis_job(
+                pool.get_ref().clone(),
+                jobs.clone(),
+                job_id,
+                book_ids,
+            ));
 
-                let book = Book {
-                    book_id: row.book_id,
-                    authors,
-                    title: row.title.unwrap_or_default(),
-                    language: row.language_name.unwrap_or_default(),
-                    downloads: row.downloads.unwrap_or_default(),
-                    bookshelves: Some(bookshelves),
-                    subjects: Some(subjects),
-                    category: row.category.unwrap_or_default(),
-                    content_url: row.content_url,
-                    cover_image_url_small: row.cover_image_url_small,
-                    cover_image_url_medium: row.cover_image_url_medium,
-                    analytics: None,
-                };
-                books.push(book);
+            match jobs.get(job_id) {
+                Some(status) => HttpResponse::Accepted().json(status),
+                None => HttpResponse::InternalServerError().finish(),
             }
-
-            HttpResponse::Ok().json(books)
         }
         Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
     }
 }
 
-#[get("/books/{id}")]
-pub async fn get_book(pool: web::Data<PgPool>, path: web::Path<i64>) -> impl Responder {
-    let id = path.into_inner();
-    let res = sqlx::query!(
-        r#"
-        SELECT
-            books.book_id,
-            books.title,
-            books.content_url,
-            books.downloads,
-            books.category,
-            books.cover_image_url_medium,
-            books.cover_image_url_small,
-            languages.language_name,
-            COALESCE(
-                (SELECT json_agg(json_build_object('author_id', authors.author_id, 'author_name', authors.author_name, 'year_of_birth', authors.year_of_birth, 'year_of_death', authors.year_of_death)) 
-                FROM books_authors 
-                INNER JOIN authors ON books_authors.author_id = authors.author_id 
-                WHERE books.book_id = books_authors.book_id), '[]') AS authors,
-            COALESCE(
-                (SELECT json_agg(json_build_object('subject_id', s.subject_id, 'subject_name', s.subject_name)) 
-                FROM 
-                    (SELECT DISTINCT subjects.subject_id, subjects.subject_name 
-                    FROM books_subjects 
-                    INNER JOIN subjects ON books_subjects.subject_id = subjects.subject_id 
-                    WHERE books.book_id = books_subjects.book_id) AS s), '[]') AS subjects,
-            COALESCE(
-                (SELECT json_agg(json_build_object('shelf_id', b.shelf_id, 'shelf_name', b.shelf_name)) 
-                FROM 
-                    (SELECT DISTINCT bookshelves.shelf_id, bookshelves.shelf_name 
-                    FROM books_bookshelves 
-                    INNER JOIN bookshelves ON books_bookshelves.shelf_id = bookshelves.shelf_id 
-                    WHERE books.book_id = books_bookshelves.book_id) AS b), '[]') AS bookshelves
-        FROM 
-            books
-        INNER JOIN
-            languages ON books.language_id = languages.language_id
-        WHERE book_id = $1
-        GROUP BY
-            books.book_id,
-            languages.language_name;
-        "#, id
-    ).fetch_one(&**pool).await;
+#[post("/jobs/vectors")]
+pub async fn start_vector_job(
+    pool: web::Data<PgPool>,
+    jobs: web::Data<Jobs>,
+    index: web::Data<RwLock<VectorIndex>>,
+) -> impl Responder {
+    let res = sqlx::query!("SELECT book_id FROM book_analytics ORDER BY book_id;")
+        .fetch_all(&**pool)
+        .await;
 
     match res {
-        Ok(res) => {
-            let content: String = reqwest::get(res.content_url.as_ref().unwrap())
-                .await
-                .unwrap()
-                .text()
-                .await
-                .unwrap_or_default();
+        Ok(rows) => {
+            let book_ids: Vec<i64> = rows.into_iter().map(|row| row.book_id).collect();
+            let job_id = match jobs.start(JobKind::Vectors, book_ids.len()) {
+                Ok(job_id) => job_id,
+                Err(running) => return HttpResponse::Conflict().json(running),
+            };
+            actix_web::rt::spawn(run_vector_job(
+                pool.get_ref().clone(),
+                jobs.clone(),
+                index.clone(),
+                job_id,
+                book_ids,
+            ));
 
-            let subjects_json = res
-                .subjects
-                .as_ref()
-                .map_or(String::from("[]"), |jv| jv.to_string());
-            let subjects: Vec<Subject> = serde_json::from_str(&subjects_json).unwrap();
+            match jobs.get(job_id) {
+                Some(status) => HttpResponse::Accepted().json(status),
+                None => HttpResponse::InternalServerError().finish(),
+            }
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
 
-            let bookshelves_json = res
-                .bookshelves
-                .as_ref()
-                .map_or(String::from("[]"), |jv| jv.to_string());
-            let bookshelves: Vec<Bookshelf> = serde_json::from_str(&bookshelves_json).unwrap();
+#[post("/jobs/topics")]
+pub async fn start_topic_job(
+    pool: web::Data<PgPool>,
+    jobs: web::Data<Jobs>,
+    query: web::Query<TopicJobQuery>,
+) -> impl Responder {
+    let topics = query.topics.unwrap_or(DEFAULT_TOPICS);
+    if !(2..=MAX_TOPICS).contains(&topics) {
+        return HttpResponse::BadRequest()
+            .body(format!("topics must be between 2 and {}", MAX_TOPICS));
+    }
 
-            let authors_json = res
-                .authors
-                .as_ref()
-                .map_or(String::from("[]"), |jv| jv.to_string());
-            let authors: Vec<Author> = serde_json::from_str(&authors_json).unwrap();
+    let res = sqlx::query!("SELECT book_id FROM book_analytics ORDER BY book_id;")
+        .fetch_all(&**pool)
+        .await;
 
-            let book = Book {
-                book_id: res.book_id,
-                authors,
-                title: res.title.unwrap_or_default(),
-                language: res.language_name.unwrap_or_default(),
-                downloads: res.downloads.unwrap_or_default(),
-                bookshelves: Some(bookshelves),
-                subjects: Some(subjects),
-                category: res.category.unwrap_or_default(),
-                content_url: res.content_url,
-                cover_image_url_small: res.cover_image_url_small,
-                cover_image_url_medium: res.cover_image_url_medium,
-                analytics: Some(Analytics::new(&content)),
+    match res {
+        Ok(rows) => {
+            let book_ids: Vec<i64> = rows.into_iter().map(|row| row.book_id).collect();
+            let job_id = match jobs.start(JobKind::Topics, book_ids.len()) {
+                Ok(job_id) => job_id,
+                Err(running) => return HttpResponse::Conflict().json(running),
             };
+            actix_web::rt::spawn(run_topic_job(
+                pool.get_ref().clone(),
+                jobs.clone(),
+                job_id,
+                book_ids,
+                topics,
+            ));
 
-            HttpResponse::Ok().json(book)
+            match jobs.get(job_id) {
+                Some(status) => HttpResponse::Accepted().json(status),
+                None => HttpResponse::InternalServerError().finish(),
+            }
         }
         Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
     }
 }
 
+#[post("/jobs/auto-shelves")]
+pub async fn start_auto_shelf_job(
+    pool: web::Data<PgPool>,
+    jobs: web::Data<Jobs>,
+    index: web::Data<RwLock<VectorIndex>>,
+    query: web::Query<AutoShelfJobQuery>,
+) -> impl Responder {
+    let shelves = query.shelves.unwrap_or(DEFAULT_AUTO_SHELVES);
+    if !(2..=MAX_AUTO_SHELVES).contains(&shelves) {
+        return HttpResponse::BadRequest().body(format!(
+            "shelves must be between 2 and {}",
+            MAX_AUTO_SHELVES
+        ));
+    }
+
+    let index = index.read().unwrap().clone();
+    if index.len() < shelves {
+        return HttpResponse::BadRequest().body(format!(
+            "Only {} books have content vectors; run /jobs/vectors first",
+            index.len()
+        ));
+    }
+
+    let job_id = match jobs.start(JobKind::AutoShelves, index.len()) {
+        Ok(job_id) => job_id,
+        Err(running) => return HttpResponse::Conflict().json(running),
+    };
+    actix_web::rt::spawn(run_auto_shelf_job(
+        pool.get_ref().clone(),
+        jobs.clone(),
+        job_id,
+        index,
+        shelves,
+    ));
+
+    match jobs.get(job_id) {
+        Some(status) => HttpResponse::Accepted().json(status),
+        None => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/jobs/{id}")]
+pub async fn get_job(jobs: web::Data<Jobs>, path: web::Path<u64>) -> impl Responder {
+    match jobs.get(path.into_inner()) {
+        Some(status) => HttpResponse::Ok().json(status),
+        None => HttpResponse::NotFound().body("Job not found"),
+    }
+}
+
+#[get("/books/{id}/text")]
+pub async fn get_text(
+    req: HttpRequest,
+    pool: web::Data<PgPool>,
+    path: web::Path<i64>,
+    query: web::Query<TextQuery>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let content = match fetch_book_content(&pool, id).await {
+        Ok(content) => content,
+        Err(response) => return response,
+    };
+    let body = content.as_str();
+
+    let mut response = HttpResponse::Ok().finish();
+    let selection = if let Some(chapter) = query.chapter {
+        match split_chapters(body).into_iter().nth(chapter) {
+            Some((_, text)) => text,
+            None => return HttpResponse::NotFound().body("Chapter not found"),
+        }
+    } else if query.start.is_some() || query.end.is_some() {
+        char_slice(
+            body,
+            query.start.unwrap_or(0),
+            query.end.unwrap_or(usize::MAX),
+        )
+    } else {
+        let page_size = query
+            .page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .max(MIN_PAGE_SIZE);
+        let pages = paginate(body, page_size);
+        let page = query.page.unwrap_or(1);
+        let range = match page.checked_sub(1).and_then(|index| pages.get(index)) {
+            Some(range) => range.clone(),
+            None => return HttpResponse::NotFound().body("Page not found"),
+        };
+
+        let headers = response.headers_mut();
+        headers.insert(
+            header::HeaderName::from_static("x-total-pages"),
+            header::HeaderValue::from(pages.len()),
+        );
+        if let Ok(value) = header::HeaderValue::from_str(&format!("{}-{}", range.start, range.end))
+        {
+            headers.insert(header::HeaderName::from_static("x-character-range"), value);
+        }
+
+        char_slice(body, range.start, range.end)
+    };
+
+    text_response(&req, reflow(selection), response)
+}
+
 #[get("/subjects")]
 pub async fn get_top_subjects(pool: web::Data<PgPool>) -> impl Responder {
     let res = sqlx::query!(
@@ -254,6 +2314,145 @@ pub async fn get_top_bookshelves(pool: web::Data<PgPool>) -> impl Responder {
     }
 }
 
+#[get("/auto-shelves")]
+pub async fn get_auto_shelves(pool: web::Data<PgPool>) -> impl Responder {
+    let res = sqlx::query!(
+        r#"
+        SELECT shelf_id, shelf_name, keywords, count_of_books
+        FROM auto_shelves
+        ORDER BY count_of_books DESC, shelf_id;
+        "#
+    )
+    .fetch_all(&**pool)
+    .await;
+
+    match res {
+        Ok(rows) => {
+            let shelves: Vec<AutoShelf> = rows
+                .into_iter()
+                .map(|row| AutoShelf {
+                    shelf_id: row.shelf_id,
+                    shelf_name: row.shelf_name,
+                    keywords: row.keywords,
+                    count_of_books: row.count_of_books,
+                    generated: true,
+                })
+                .collect();
+            HttpResponse::Ok().json(shelves)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
+#[get("/auto-shelves/{shelf_id}")]
+pub async fn get_books_from_auto_shelf(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> impl Responder {
+    let shelf_id = path.into_inner();
+    let res = sqlx::query!(
+        r#"
+        SELECT
+            books.book_id,
+            books.title,
+            books.content_url,
+            books.downloads,
+            books.category,
+            books.cover_image_url_medium,
+            books.cover_image_url_small,
+            languages.language_name AS "language_name?",
+            COALESCE(
+                (SELECT json_agg(json_build_object('author_id', authors.author_id, 'author_name', authors.author_name, 'year_of_birth', authors.year_of_birth, 'year_of_death', authors.year_of_death))
+                FROM books_authors
+                INNER JOIN authors ON books_authors.author_id = authors.author_id
+                WHERE books.book_id = books_authors.book_id), '[]') AS authors,
+            COALESCE(
+                (SELECT json_agg(json_build_object('subject_id', s.subject_id, 'subject_name', s.subject_name))
+                FROM
+                    (SELECT DISTINCT subjects.subject_id, subjects.subject_name
+                    FROM books_subjects
+                    INNER JOIN subjects ON books_subjects.subject_id = subjects.subject_id
+                    WHERE books.book_id = books_subjects.book_id) AS s), '[]') AS subjects,
+            COALESCE(
+                (SELECT json_agg(json_build_object('shelf_id', b.shelf_id, 'shelf_name', b.shelf_name))
+                FROM
+                    (SELECT DISTINCT bookshelves.shelf_id, bookshelves.shelf_name
+                    FROM books_bookshelves
+                    INNER JOIN bookshelves ON books_bookshelves.shelf_id = bookshelves.shelf_id
+                    WHERE books.book_id = books_bookshelves.book_id) AS b), '[]') AS bookshelves
+        FROM
+            books
+        LEFT JOIN
+            languages ON books.language_id = languages.language_id
+        INNER JOIN
+            books_auto_shelves ON books.book_id = books_auto_shelves.book_id
+        WHERE books_auto_shelves.shelf_id = $1
+        ORDER BY
+            books_auto_shelves.similarity DESC;
+        "#,
+        shelf_id
+    )
+    .fetch_all(&**pool)
+    .await;
+
+    match res {
+        Ok(rows) => {
+            // Most representative books first.
+            let books: Vec<Book> = rows
+                .into_iter()
+                .map(|row| Book {
+                    book_id: row.book_id,
+                    authors: serde_json::from_value(row.authors.unwrap_or_default())
+                        .unwrap_or_default(),
+                    title: row.title.unwrap_or_default(),
+                    language: row.language_name.unwrap_or_default(),
+                    downloads: row.downloads.unwrap_or_default(),
+                    bookshelves: serde_json::from_value(row.bookshelves.unwrap_or_default()).ok(),
+                    subjects: serde_json::from_value(row.subjects.unwrap_or_default()).ok(),
+                    category: row.category.unwrap_or_default(),
+                    content_url: row.content_url,
+                    cover_image_url_small: row.cover_image_url_small,
+                    cover_image_url_medium: row.cover_image_url_medium,
+                    analytics: None,
+                })
+                .collect();
+            HttpResponse::Ok().json(books)
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
+#[get("/bookshelves/{shelf_id}/analytics")]
+pub async fn get_bookshelf_analytics(
+    pool: web::Data<PgPool>,
+    jobs: web::Data<Jobs>,
+    path: web::Path<i32>,
+    query: web::Query<AggregateQuery>,
+) -> impl Responder {
+    let shelf_id = path.into_inner();
+    let res = sqlx::query!(
+        r#"
+        SELECT DISTINCT books.book_id
+        FROM books
+        INNER JOIN books_bookshelves ON books.book_id = books_bookshelves.book_id
+        WHERE books_bookshelves.shelf_id = $1 AND books.content_url IS NOT NULL
+        ORDER BY books.book_id;
+        "#,
+        shelf_id
+    )
+    .fetch_all(&**pool)
+    .await;
+
+    match res {
+        Ok(rows) => {
+            let book_ids = rows.into_iter().map(|row| row.book_id).collect();
+            let top_words = query.top.unwrap_or(DEFAULT_AGGREGATE_TOP_WORDS);
+            aggregate_analytics(&pool, &jobs, book_ids, top_words).await
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
 #[get("/bookshelves/{shelf_id}")]
 pub async fn get_books_from_bookshelf(
     pool: web::Data<PgPool>,
@@ -351,6 +2550,37 @@ pub async fn get_books_from_bookshelf(
     }
 }
 
+#[get("/subjects/{subject_id}/analytics")]
+pub async fn get_subject_analytics(
+    pool: web::Data<PgPool>,
+    jobs: web::Data<Jobs>,
+    path: web::Path<i32>,
+    query: web::Query<AggregateQuery>,
+) -> impl Responder {
+    let subject_id = path.into_inner();
+    let res = sqlx::query!(
+        r#"
+        SELECT DISTINCT books.book_id
+        FROM books
+        INNER JOIN books_subjects ON books.book_id = books_subjects.book_id
+        WHERE books_subjects.subject_id = $1 AND books.content_url IS NOT NULL
+        ORDER BY books.book_id;
+        "#,
+        subject_id
+    )
+    .fetch_all(&**pool)
+    .await;
+
+    match res {
+        Ok(rows) => {
+            let book_ids = rows.into_iter().map(|row| row.book_id).collect();
+            let top_words = query.top.unwrap_or(DEFAULT_AGGREGATE_TOP_WORDS);
+            aggregate_analytics(&pool, &jobs, book_ids, top_words).await
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
 #[get("/subjects/{subject_id}")]
 pub async fn get_books_of_subject(pool: web::Data<PgPool>, path: web::Path<i32>) -> impl Responder {
     let subject_id = path.into_inner();
@@ -445,6 +2675,45 @@ pub async fn get_books_of_subject(pool: web::Data<PgPool>, path: web::Path<i32>)
     }
 }
 
+#[get("/authors/{author_id}/analytics")]
+pub async fn get_author_analytics(
+    pool: web::Data<PgPool>,
+    jobs: web::Data<Jobs>,
+    path: web::Path<i32>,
+    query: web::Query<AggregateQuery>,
+) -> impl Responder {
+    let author_id = path.into_inner();
+    let res = sqlx::query!(
+        r#"
+        SELECT DISTINCT books.book_id
+        FROM books
+        INNER JOIN books_authors ON books.book_id = books_authors.book_id
+        WHERE books_authors.author_id = $1 AND books.content_url IS NOT NULL
+        ORDER BY books.book_id;
+        "#,
+        author_id
+    )
+    .fetch_all(&**pool)
+    .await;
+
+    match res {
+        Ok(rows) => {
+            let book_ids = rows.into_iter().map(|row| row.book_id).collect();
+            let top_words = query.top.unwrap_or(DEFAULT_AGGREGATE_TOP_WORDS);
+            aggregate_analytics(&pool, &jobs, book_ids, top_words).await
+        }
+        Err(e) => HttpResponse::InternalServerError().body(format!("Error occurred{:?}", e)),
+    }
+}
+
+#[get("/authors/{author_id}/style")]
+pub async fn get_author_style(pool: web::Data<PgPool>, path: web::Path<i32>) -> impl Responder {
+    match author_style(&pool, path.into_inner(), None).await {
+        Ok(style) => HttpResponse::Ok().json(style),
+        Err(response) => response,
+    }
+}
+
 #[get("/authors/{author_id}")]
 pub async fn get_books_from_author(
     pool: web::Data<PgPool>,